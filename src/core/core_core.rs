@@ -1,4 +1,5 @@
 use bevy::app::App;
+use bevy_egui::EguiContexts;
 use std::fmt;
 
 use crate::core::*;
@@ -9,13 +10,20 @@ impl Plugin for CorePlugin {
     fn build(&self, app: &mut App) {
         app.add_plugins(CoreGuiPlugin)
             .insert_resource(GameState::default())
-            .add_systems(Update, mode_switching_system);
+            .add_systems(Update, mode_switching_system.in_set(ModeRequestSet))
+            .add_systems(Update, apply_requested_mode.after(ModeRequestSet));
     }
 }
 
+/// Systems that set `GameState::requested_mode`, the request is only checked and applied once all of them ran.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, SystemSet)]
+pub struct ModeRequestSet;
+
 #[derive(Resource)]
 pub struct GameState {
     pub mode: GameMode,
+    /// Mode asked for by the user, applied by `apply_requested_mode` unless a plugin takes it first.
+    pub requested_mode: Option<GameMode>,
 }
 
 impl fmt::Display for GameMode {
@@ -23,6 +31,9 @@ impl fmt::Display for GameMode {
         write!(f, "{}", match self {
             GameMode::Editor => "Editor",
             GameMode::Game => "Game",
+            GameMode::Replay => "Replay",
+            GameMode::Netplay => "Netplay",
+            GameMode::Training => "Training",
         })
     }
 }
@@ -31,22 +42,49 @@ impl fmt::Display for GameMode {
 pub enum GameMode {
     Editor,
     Game,
+    /// Plays back a recorded match instead of reading the controllers.
+    Replay,
+    /// Plays against another instance over the network with rollback.
+    Netplay,
+    /// Practices against a configurable dummy in place of player two.
+    Training,
+}
+
+impl GameMode {
+    /// Modes running the fighting game simulation.
+    pub fn is_simulated(&self) -> bool {
+        matches!(self, GameMode::Game | GameMode::Replay | GameMode::Netplay | GameMode::Training)
+    }
 }
 
 impl Default for GameState {
     fn default() -> Self {
         GameState {
             mode: GameMode::Editor,
+            requested_mode: None,
         }
     }
 }
 
 fn mode_switching_system(
+    mut egui_contexts: EguiContexts,
     mut game_state: ResMut<GameState>,
     keyboard: Res<ButtonInput<KeyCode>>) {
+    if egui_contexts.ctx_mut().wants_keyboard_input() {
+        return;
+    }
+
     if keyboard.just_pressed(KeyCode::KeyE) {
-        game_state.mode = GameMode::Editor;
+        game_state.requested_mode = Some(GameMode::Editor);
     } else if keyboard.just_pressed(KeyCode::KeyG) {
-        game_state.mode = GameMode::Game;
+        game_state.requested_mode = Some(GameMode::Game);
+    } else if keyboard.just_pressed(KeyCode::KeyT) {
+        game_state.requested_mode = Some(GameMode::Training);
+    }
+}
+
+pub fn apply_requested_mode(mut game_state: ResMut<GameState>) {
+    if let Some(mode) = game_state.requested_mode.take() {
+        game_state.mode = mode;
     }
 }
\ No newline at end of file
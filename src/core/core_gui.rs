@@ -3,17 +3,15 @@ use bevy_egui::{egui, EguiContexts};
 
 use crate::core::*;
 use crate::core::core_core::*;
+use crate::editor::editor_core::EditorBoxKind;
 
 pub struct CoreGuiPlugin;
 
 impl Plugin for CoreGuiPlugin {
     fn build(&self, app: &mut App) {
         app
-            .insert_resource(CoreGuiState {
-                show_hit_boxes: false,
-                show_hurt_boxes: false,
-            })
-            .add_systems(Update, display_core_information);
+            .insert_resource(CoreGuiState::default())
+            .add_systems(Update, display_core_information.in_set(ModeRequestSet));
     }
 }
 
@@ -21,6 +19,39 @@ impl Plugin for CoreGuiPlugin {
 pub struct CoreGuiState {
     pub show_hit_boxes: bool,
     pub show_hurt_boxes: bool,
+    pub show_push_boxes: bool,
+    pub show_throw_boxes: bool,
+    pub show_throwable_boxes: bool,
+    pub show_proximity_guard_boxes: bool,
+    pub show_projectile_clash_boxes: bool,
+    pub show_input_display: bool,
+    pub show_frame_advantage: bool,
+}
+
+impl CoreGuiState {
+    pub fn shows_boxes(&self, kind: EditorBoxKind) -> bool {
+        match kind {
+            EditorBoxKind::Hit => self.show_hit_boxes,
+            EditorBoxKind::Hurt => self.show_hurt_boxes,
+            EditorBoxKind::Push => self.show_push_boxes,
+            EditorBoxKind::Throw => self.show_throw_boxes,
+            EditorBoxKind::Throwable => self.show_throwable_boxes,
+            EditorBoxKind::ProximityGuard => self.show_proximity_guard_boxes,
+            EditorBoxKind::ProjectileClash => self.show_projectile_clash_boxes,
+        }
+    }
+
+    pub fn shows_boxes_mut(&mut self, kind: EditorBoxKind) -> &mut bool {
+        match kind {
+            EditorBoxKind::Hit => &mut self.show_hit_boxes,
+            EditorBoxKind::Hurt => &mut self.show_hurt_boxes,
+            EditorBoxKind::Push => &mut self.show_push_boxes,
+            EditorBoxKind::Throw => &mut self.show_throw_boxes,
+            EditorBoxKind::Throwable => &mut self.show_throwable_boxes,
+            EditorBoxKind::ProximityGuard => &mut self.show_proximity_guard_boxes,
+            EditorBoxKind::ProjectileClash => &mut self.show_projectile_clash_boxes,
+        }
+    }
 }
 
 fn display_core_information(
@@ -38,19 +69,27 @@ fn display_core_information(
             };
 
             if ui.button(button_text).clicked() {
-                game_state.mode = if game_state.mode == GameMode::Editor {
+                game_state.requested_mode = Some(if game_state.mode == GameMode::Editor {
                     GameMode::Game
                 } else {
                     GameMode::Editor
-                };
+                });
             };
 
+            if game_state.mode == GameMode::Editor && ui.button("Training").clicked() {
+                game_state.requested_mode = Some(GameMode::Training);
+            }
+
             ui.label(format!("Mode: {}", game_state.mode));
         });
 
-        if game_state.mode == GameMode::Game {
-            ui.checkbox(&mut gui_state.show_hit_boxes, "Show Hit Boxes");
-            ui.checkbox(&mut gui_state.show_hurt_boxes, "Show Hurt Boxes");
+        if game_state.mode.is_simulated() {
+            for kind in EditorBoxKind::ALL {
+                ui.checkbox(gui_state.shows_boxes_mut(kind), format!("Show {}es", kind.label()));
+            }
         }
+
+        ui.checkbox(&mut gui_state.show_input_display, "Show Input Display");
+        ui.checkbox(&mut gui_state.show_frame_advantage, "Show Frame Advantage");
     });
 }
\ No newline at end of file
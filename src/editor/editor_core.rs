@@ -11,15 +11,132 @@ use serde::{Deserialize, Serialize};
 use crate::core::core_core::*;
 use crate::editor::*;
 use crate::editor::editor_gui::*;
+use crate::editor::history::history_core::HistoryPlugin;
 use crate::editor::inspector::inspector_core::SelectedFrame;
+use crate::editor::timeline::timeline_core::TimelinePlugin;
+use crate::editor::viewport::viewport_core::ViewportPlugin;
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+/// Ticks a frame lasts when the JSON does not say, 0.2 s at 60 Hz.
+pub const DEFAULT_FRAME_DURATION: u32 = 12;
+
+fn default_frame_duration() -> u32 {
+    DEFAULT_FRAME_DURATION
+}
+
+/// Part of a move a frame belongs to, in the fighting game frame data sense.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum EditorFramePhase {
+    /// Not part of an attack, e.g. idle or movement frames.
+    #[default]
+    Neutral,
+    Startup,
+    Active,
+    Recovery,
+}
+
+impl EditorFramePhase {
+    pub const ALL: [EditorFramePhase; 4] = [
+        EditorFramePhase::Neutral,
+        EditorFramePhase::Startup,
+        EditorFramePhase::Active,
+        EditorFramePhase::Recovery,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            EditorFramePhase::Neutral => "Neutral",
+            EditorFramePhase::Startup => "Startup",
+            EditorFramePhase::Active => "Active",
+            EditorFramePhase::Recovery => "Recovery",
+        }
+    }
+
+    pub fn color(&self) -> Color {
+        match self {
+            EditorFramePhase::Neutral => Color::GRAY,
+            EditorFramePhase::Startup => Color::rgb_u8(137, 180, 250),
+            EditorFramePhase::Active => Color::rgb_u8(243, 139, 168),
+            EditorFramePhase::Recovery => Color::rgb_u8(249, 226, 175),
+        }
+    }
+}
+
+/// Startup, active and recovery lengths of a sheet, in 60 Hz ticks.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct EditorPhaseSummary {
+    pub startup: u32,
+    pub active: u32,
+    pub recovery: u32,
+}
+
+impl EditorPhaseSummary {
+    pub fn total(&self) -> u32 {
+        self.startup + self.active + self.recovery
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct EditorFrameData {
-    pub hit_boxes: Vec<EditorHitBox>,
-    pub hurt_boxes: Vec<EditorHurtBox>,
+    /// How long the frame is shown, in 60 Hz ticks.
+    #[serde(default = "default_frame_duration")]
+    pub duration: u32,
+    #[serde(default)]
+    pub phase: EditorFramePhase,
+    pub hit_boxes: Vec<EditorBox>,
+    pub hurt_boxes: Vec<EditorBox>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub push_boxes: Vec<EditorBox>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub throw_boxes: Vec<EditorBox>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub throwable_boxes: Vec<EditorBox>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub proximity_guard_boxes: Vec<EditorBox>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub projectile_clash_boxes: Vec<EditorBox>,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum EditorClipLoopMode {
+    /// Plays through once, then reports the clip as finished.
+    #[default]
+    Once,
+    Loop,
+    PingPong,
+    /// Plays through once and stays on the last frame.
+    HoldLast,
+}
+
+impl EditorClipLoopMode {
+    pub const ALL: [EditorClipLoopMode; 4] = [
+        EditorClipLoopMode::Once,
+        EditorClipLoopMode::Loop,
+        EditorClipLoopMode::PingPong,
+        EditorClipLoopMode::HoldLast,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            EditorClipLoopMode::Once => "Once",
+            EditorClipLoopMode::Loop => "Loop",
+            EditorClipLoopMode::PingPong => "Ping-Pong",
+            EditorClipLoopMode::HoldLast => "Hold Last",
+        }
+    }
+}
+
+/// A named range of frames of a sheet, `start` and `end` are inclusive.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct EditorAnimationClip {
+    pub name: String,
+    pub start: usize,
+    pub end: usize,
+    pub loop_mode: EditorClipLoopMode,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct EditorSpriteSheetInfo {
     pub id: String,
     pub image_path: String,
@@ -30,6 +147,138 @@ pub struct EditorSpriteSheetInfo {
     pub columns: usize,
     pub rows: usize,
     pub frames: Vec<EditorFrameData>,
+    #[serde(default)]
+    pub clips: Vec<EditorAnimationClip>,
+}
+
+impl EditorSpriteSheetInfo {
+    /// Clip covering every frame of the sheet.
+    pub fn whole_sheet_clip(&self, loop_mode: EditorClipLoopMode) -> EditorAnimationClip {
+        EditorAnimationClip {
+            name: "All Frames".to_string(),
+            start: 0,
+            end: self.frames.len().saturating_sub(1),
+            loop_mode,
+        }
+    }
+
+    /// The clip played when nothing asks for a specific one: the first named clip, or the whole sheet looping.
+    pub fn default_clip(&self) -> EditorAnimationClip {
+        self.clips.first()
+            .cloned()
+            .unwrap_or_else(|| self.whole_sheet_clip(EditorClipLoopMode::Loop))
+    }
+
+    pub fn frame_duration(&self, frame_index: usize) -> u32 {
+        self.frames.get(frame_index).map_or(DEFAULT_FRAME_DURATION, |frame| frame.duration).max(1)
+    }
+
+    /// Sums the durations of the frames tagged with each phase.
+    pub fn phase_summary(&self) -> EditorPhaseSummary {
+        let mut summary = EditorPhaseSummary::default();
+        for (frame_index, frame) in self.frames.iter().enumerate() {
+            let duration = self.frame_duration(frame_index);
+            match frame.phase {
+                EditorFramePhase::Neutral => {}
+                EditorFramePhase::Startup => summary.startup += duration,
+                EditorFramePhase::Active => summary.active += duration,
+                EditorFramePhase::Recovery => summary.recovery += duration,
+            }
+        }
+        summary
+    }
+
+    /// Phases derived from the hit boxes: frames with a hit box are active, the frames before
+    /// the first active one are startup and everything after it is recovery.
+    /// Sheets without any hit box are left neutral.
+    pub fn suggested_phases(&self) -> Vec<EditorFramePhase> {
+        let is_active = |frame: &EditorFrameData| frame.active_boxes(EditorBoxKind::Hit).next().is_some();
+        let Some(first_active) = self.frames.iter().position(is_active) else {
+            return vec![EditorFramePhase::Neutral; self.frames.len()];
+        };
+
+        self.frames.iter()
+            .enumerate()
+            .map(|(frame_index, frame)| if is_active(frame) {
+                EditorFramePhase::Active
+            } else if frame_index < first_active {
+                EditorFramePhase::Startup
+            } else {
+                EditorFramePhase::Recovery
+            })
+            .collect()
+    }
+}
+
+/// Playback position inside a clip, advanced one 60 Hz tick at a time.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ClipPlayback {
+    pub clip: EditorAnimationClip,
+    pub frame: usize,
+    /// Ticks already spent on `frame`.
+    pub ticks: u32,
+    /// Set while a ping-pong clip plays backwards.
+    pub reverse: bool,
+    /// Set once a `Once` clip went past its last frame.
+    pub finished: bool,
+}
+
+impl ClipPlayback {
+    pub fn new(clip: EditorAnimationClip) -> Self {
+        let frame = clip.start;
+        ClipPlayback::starting_at(clip, frame)
+    }
+
+    pub fn starting_at(clip: EditorAnimationClip, frame: usize) -> Self {
+        let frame = frame.clamp(clip.start, clip.end.max(clip.start));
+        ClipPlayback {
+            clip,
+            frame,
+            ticks: 0,
+            reverse: false,
+            finished: false,
+        }
+    }
+
+    /// Advances by one tick, returns whether the displayed frame changed.
+    pub fn tick(&mut self, sheet_info: &EditorSpriteSheetInfo) -> bool {
+        if self.finished {
+            return false;
+        }
+
+        self.ticks += 1;
+        if self.ticks < sheet_info.frame_duration(self.frame) {
+            return false;
+        }
+        self.ticks = 0;
+
+        let start = self.clip.start;
+        let end = self.clip.end.max(start).min(sheet_info.frames.len().saturating_sub(1));
+        let at_end = self.frame >= end;
+
+        let next = match self.clip.loop_mode {
+            EditorClipLoopMode::Once if at_end => {
+                self.finished = true;
+                return false;
+            }
+            EditorClipLoopMode::HoldLast if at_end => return false,
+            EditorClipLoopMode::Loop if at_end => start,
+            EditorClipLoopMode::PingPong if start >= end => return false,
+            EditorClipLoopMode::PingPong => {
+                if self.reverse && self.frame <= start {
+                    self.reverse = false;
+                } else if !self.reverse && at_end {
+                    self.reverse = true;
+                }
+                if self.reverse { self.frame - 1 } else { self.frame + 1 }
+            }
+            _ => self.frame + 1,
+        };
+
+        let changed = next != self.frame;
+        self.frame = next;
+        changed
+    }
 }
 
 
@@ -54,26 +303,371 @@ pub struct EditorSpriteSheetsData {
 #[derive(Resource)]
 pub struct EditorSpriteSheetAtlas {
     pub handle: Handle<TextureAtlasLayout>,
-    pub sprite_sheet_path: String,
     pub texture_handle: Handle<Image>,
+    /// Working copy of the sheet, every edit made in the editor lands here.
     pub sprite_sheet_info: EditorSpriteSheetInfo,
+    /// The sheet as it was last loaded from or saved to disk.
+    pub saved_sprite_sheet_info: EditorSpriteSheetInfo,
+}
+
+impl EditorSpriteSheetAtlas {
+    pub fn is_modified(&self) -> bool {
+        self.sprite_sheet_info != self.saved_sprite_sheet_info
+    }
 }
 
 #[derive(Resource)]
 pub(crate) struct EditorSpriteSheets {
     pub(crate) sheets: HashMap<String, EditorSpriteSheetAtlas>,
+    /// Sheet ids in the order they appear in the JSON file.
+    pub(crate) order: Vec<String>,
 }
 
-#[derive(Default, Component, Serialize, Deserialize, Clone, Debug)]
-pub struct EditorHitBox {
-    pub size: Vec2,
-    pub offset: Vec2,
+impl EditorSpriteSheets {
+    pub fn sheet_info(&self, id: &str) -> Option<&EditorSpriteSheetInfo> {
+        self.sheets.get(id).map(|atlas| &atlas.sprite_sheet_info)
+    }
+
+    pub fn sheet_info_mut(&mut self, id: &str) -> Option<&mut EditorSpriteSheetInfo> {
+        self.sheets.get_mut(id).map(|atlas| &mut atlas.sprite_sheet_info)
+    }
+
+    pub fn selected_frame_data(&self, selected_frame: &SelectedFrame) -> Option<&EditorFrameData> {
+        let sheet_info = self.sheet_info(selected_frame.sprite_sheet_id.as_ref()?)?;
+        sheet_info.frames.get(selected_frame.frame_index?)
+    }
+
+    pub fn selected_frame_data_mut(&mut self, selected_frame: &SelectedFrame) -> Option<&mut EditorFrameData> {
+        let sheet_info = self.sheet_info_mut(selected_frame.sprite_sheet_id.as_ref()?)?;
+        sheet_info.frames.get_mut(selected_frame.frame_index?)
+    }
+
+    /// Iterates the sheets in file order.
+    pub fn iter(&self) -> impl Iterator<Item = &EditorSpriteSheetAtlas> {
+        self.order.iter().filter_map(|id| self.sheets.get(id))
+    }
+
+    pub fn is_modified(&self) -> bool {
+        self.sheets.values().any(|atlas| atlas.is_modified())
+    }
+
+    /// Builds the data written to disk, sheets not listed in `ids` are written as last saved.
+    pub fn data_for_saving(&self, ids: &[String]) -> EditorSpriteSheetsData {
+        let sheets = self.iter()
+            .map(|atlas| if ids.contains(&atlas.sprite_sheet_info.id) {
+                atlas.sprite_sheet_info.clone()
+            } else {
+                atlas.saved_sprite_sheet_info.clone()
+            })
+            .collect();
+
+        EditorSpriteSheetsData { sheets }
+    }
+
+    /// Writes the given sheets to disk and marks them as saved.
+    pub fn save(&mut self, ids: &[String]) {
+        let data_to_save = self.data_for_saving(ids);
+        save_settings_to_file(SPRITE_SHEETS_PATH, &data_to_save);
+
+        for id in ids {
+            if let Some(atlas) = self.sheets.get_mut(id) {
+                atlas.saved_sprite_sheet_info = atlas.sprite_sheet_info.clone();
+            }
+        }
+    }
+
+    pub fn save_all(&mut self) {
+        let ids = self.order.clone();
+        self.save(&ids);
+    }
+
+    /// Throws away the unsaved edits of every sheet.
+    pub fn discard_all(&mut self) {
+        for atlas in self.sheets.values_mut() {
+            atlas.sprite_sheet_info = atlas.saved_sprite_sheet_info.clone();
+        }
+    }
+}
+
+pub const SPRITE_SHEETS_PATH: &str = "assets/sprite_sheets.json";
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum EditorHitLevel {
+    /// Blocked standing or crouching, whiffs on crouching characters in most games.
+    High,
+    #[default]
+    Mid,
+    /// Must be blocked crouching.
+    Low,
+    /// Must be blocked standing.
+    Overhead,
+}
+
+impl EditorHitLevel {
+    pub const ALL: [EditorHitLevel; 4] = [
+        EditorHitLevel::High,
+        EditorHitLevel::Mid,
+        EditorHitLevel::Low,
+        EditorHitLevel::Overhead,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            EditorHitLevel::High => "High",
+            EditorHitLevel::Mid => "Mid",
+            EditorHitLevel::Low => "Low",
+            EditorHitLevel::Overhead => "Overhead",
+        }
+    }
+}
+
+/// What happens when a hit box connects. Durations are in 60 Hz ticks.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct EditorHitProperties {
+    pub damage: u32,
+    /// Damage dealt when the hit is blocked.
+    pub chip_damage: u32,
+    pub hitstun: u32,
+    pub blockstun: u32,
+    /// Freeze applied to both characters when the hit connects.
+    pub hitstop: u32,
+    /// Velocity given to the defender in sprite pixels per tick, x pointing away from the attacker.
+    pub knockback: Vec2,
+    pub hit_level: EditorHitLevel,
+    /// Bounces a juggled defender off the ground once instead of knocking them down.
+    pub ground_bounce: bool,
+    /// Bounces a juggled defender off the wall once instead of sliding down it.
+    pub wall_bounce: bool,
+    /// Boxes sharing a group belong to the same hit and connect at most once per attack.
+    pub hit_group: u32,
 }
 
-#[derive(Default, Component, Serialize, Deserialize, Clone, Debug)]
-pub struct EditorHurtBox {
+impl Default for EditorHitProperties {
+    fn default() -> Self {
+        EditorHitProperties {
+            damage: 10,
+            chip_damage: 0,
+            hitstun: 12,
+            blockstun: 8,
+            hitstop: 8,
+            knockback: Vec2::new(2.0, 0.0),
+            hit_level: EditorHitLevel::Mid,
+            ground_bounce: false,
+            wall_bounce: false,
+            hit_group: 0,
+        }
+    }
+}
+
+/// A box of any kind, positioned by its center `offset` from the sprite center, in sprite pixels.
+#[derive(Default, Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct EditorBox {
     pub size: Vec2,
     pub offset: Vec2,
+    /// Only used by the kinds that hit, see `EditorBoxKind::has_hit_properties`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub properties: Option<EditorHitProperties>,
+}
+
+impl EditorBox {
+    /// Zero-sized boxes are placeholders and never take part in gameplay.
+    pub fn is_empty(&self) -> bool {
+        self.size.x <= 0.0 || self.size.y <= 0.0
+    }
+
+    pub fn hit_properties(&self) -> EditorHitProperties {
+        self.properties.clone().unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum EditorBoxKind {
+    /// Deals damage to hurt boxes.
+    Hit,
+    /// Can be hit.
+    Hurt,
+    /// Body of the character, push boxes of two characters never overlap.
+    Push,
+    /// Grabs throwable boxes.
+    Throw,
+    /// Can be thrown.
+    Throwable,
+    /// Makes a holding-back opponent enter its guard before the attack reaches it.
+    ProximityGuard,
+    /// Cancels out the clash boxes of opposing projectiles.
+    ProjectileClash,
+}
+
+impl EditorBoxKind {
+    pub const ALL: [EditorBoxKind; 7] = [
+        EditorBoxKind::Hit,
+        EditorBoxKind::Hurt,
+        EditorBoxKind::Push,
+        EditorBoxKind::Throw,
+        EditorBoxKind::Throwable,
+        EditorBoxKind::ProximityGuard,
+        EditorBoxKind::ProjectileClash,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            EditorBoxKind::Hit => "Hit Box",
+            EditorBoxKind::Hurt => "Hurt Box",
+            EditorBoxKind::Push => "Push Box",
+            EditorBoxKind::Throw => "Throw Box",
+            EditorBoxKind::Throwable => "Throwable Box",
+            EditorBoxKind::ProximityGuard => "Proximity Guard Box",
+            EditorBoxKind::ProjectileClash => "Projectile Clash Box",
+        }
+    }
+
+    pub fn color(&self) -> Color {
+        match self {
+            EditorBoxKind::Hit => Color::RED,
+            EditorBoxKind::Hurt => Color::GREEN,
+            EditorBoxKind::Push => Color::YELLOW,
+            EditorBoxKind::Throw => Color::BLUE,
+            EditorBoxKind::Throwable => Color::CYAN,
+            EditorBoxKind::ProximityGuard => Color::PURPLE,
+            EditorBoxKind::ProjectileClash => Color::ORANGE,
+        }
+    }
+
+    pub fn has_hit_properties(&self) -> bool {
+        matches!(self, EditorBoxKind::Hit | EditorBoxKind::Throw)
+    }
+}
+
+impl EditorFrameData {
+    pub fn boxes(&self, kind: EditorBoxKind) -> &Vec<EditorBox> {
+        match kind {
+            EditorBoxKind::Hit => &self.hit_boxes,
+            EditorBoxKind::Hurt => &self.hurt_boxes,
+            EditorBoxKind::Push => &self.push_boxes,
+            EditorBoxKind::Throw => &self.throw_boxes,
+            EditorBoxKind::Throwable => &self.throwable_boxes,
+            EditorBoxKind::ProximityGuard => &self.proximity_guard_boxes,
+            EditorBoxKind::ProjectileClash => &self.projectile_clash_boxes,
+        }
+    }
+
+    pub fn boxes_mut(&mut self, kind: EditorBoxKind) -> &mut Vec<EditorBox> {
+        match kind {
+            EditorBoxKind::Hit => &mut self.hit_boxes,
+            EditorBoxKind::Hurt => &mut self.hurt_boxes,
+            EditorBoxKind::Push => &mut self.push_boxes,
+            EditorBoxKind::Throw => &mut self.throw_boxes,
+            EditorBoxKind::Throwable => &mut self.throwable_boxes,
+            EditorBoxKind::ProximityGuard => &mut self.proximity_guard_boxes,
+            EditorBoxKind::ProjectileClash => &mut self.projectile_clash_boxes,
+        }
+    }
+
+    /// Boxes of the given kind that take part in gameplay.
+    pub fn active_boxes(&self, kind: EditorBoxKind) -> impl Iterator<Item = &EditorBox> {
+        self.boxes(kind).iter().filter(|editor_box| !editor_box.is_empty())
+    }
+
+    /// Appends a box of the given kind and returns its index.
+    pub fn push_box(&mut self, kind: EditorBoxKind, size: Vec2, offset: Vec2) -> usize {
+        let properties = kind.has_hit_properties().then(EditorHitProperties::default);
+        let boxes = self.boxes_mut(kind);
+        boxes.push(EditorBox { size, offset, properties });
+        boxes.len() - 1
+    }
+
+    pub fn remove_box(&mut self, kind: EditorBoxKind, index: usize) {
+        let boxes = self.boxes_mut(kind);
+        if index < boxes.len() {
+            boxes.remove(index);
+        }
+    }
+
+    /// Inserts a copy of the box right after it and returns the index of the copy.
+    pub fn duplicate_box(&mut self, kind: EditorBoxKind, index: usize) -> Option<usize> {
+        let boxes = self.boxes_mut(kind);
+        let editor_box = boxes.get(index)?.clone();
+        boxes.insert(index + 1, editor_box);
+        Some(index + 1)
+    }
+
+    pub fn swap_boxes(&mut self, kind: EditorBoxKind, a: usize, b: usize) {
+        let boxes = self.boxes_mut(kind);
+        if a < boxes.len() && b < boxes.len() {
+            boxes.swap(a, b);
+        }
+    }
+
+    /// Drops the zero-sized placeholder boxes.
+    pub fn remove_empty_boxes(&mut self) {
+        for kind in EditorBoxKind::ALL {
+            self.boxes_mut(kind).retain(|editor_box| !editor_box.is_empty());
+        }
+    }
+}
+
+#[cfg(test)]
+impl EditorFrameData {
+    /// Frame without any box, for tests to add theirs.
+    pub fn empty() -> Self {
+        EditorFrameData {
+            duration: DEFAULT_FRAME_DURATION,
+            phase: EditorFramePhase::default(),
+            hit_boxes: Vec::new(),
+            hurt_boxes: Vec::new(),
+            push_boxes: Vec::new(),
+            throw_boxes: Vec::new(),
+            throwable_boxes: Vec::new(),
+            proximity_guard_boxes: Vec::new(),
+            projectile_clash_boxes: Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+impl EditorSpriteSheets {
+    /// Sheets without textures, for tests that only need their frame data.
+    pub fn from_sheet_infos(sheet_infos: Vec<EditorSpriteSheetInfo>) -> Self {
+        let order = sheet_infos.iter().map(|sheet_info| sheet_info.id.clone()).collect();
+        let sheets = sheet_infos.into_iter()
+            .map(|sheet_info| (sheet_info.id.clone(), EditorSpriteSheetAtlas {
+                handle: Handle::default(),
+                texture_handle: Handle::default(),
+                saved_sprite_sheet_info: sheet_info.clone(),
+                sprite_sheet_info: sheet_info,
+            }))
+            .collect();
+        EditorSpriteSheets { sheets, order }
+    }
+}
+
+#[cfg(test)]
+impl EditorSpriteSheetInfo {
+    /// Sheet of the given frames without an image behind it.
+    pub fn from_frames(id: &str, frames: Vec<EditorFrameData>) -> Self {
+        EditorSpriteSheetInfo {
+            id: id.to_string(),
+            image_path: String::new(),
+            sprite_sheet_width: 0,
+            sprite_sheet_height: 0,
+            tile_width: 0,
+            tile_height: 0,
+            columns: frames.len(),
+            rows: 1,
+            frames,
+            clips: Vec::new(),
+        }
+    }
+}
+
+/// Scale applied to the sprite displayed in the editor viewport.
+pub const EDITOR_SPRITE_SCALE: f32 = 6.0;
+
+/// Transform of the sprite displayed in the editor viewport, box offsets are relative to it.
+pub fn editor_sprite_transform() -> Transform {
+    Transform::from_scale(Vec3::splat(EDITOR_SPRITE_SCALE))
 }
 
 pub struct EditorPlugin;
@@ -81,7 +675,10 @@ pub struct EditorPlugin;
 impl Plugin for EditorPlugin {
     fn build(&self, app: &mut App) {
         app.add_plugins(EditorGuiPlugin)
-            .insert_resource(EditorSpriteSheets { sheets: HashMap::new() })
+            .add_plugins(ViewportPlugin)
+            .add_plugins(HistoryPlugin)
+            .add_plugins(TimelinePlugin)
+            .insert_resource(EditorSpriteSheets { sheets: HashMap::new(), order: Vec::new() })
             .insert_resource(EditorSpriteSheet::default())
             .insert_resource(EditorCamera::default())
             .add_systems(Startup, load_sprite_sheets)
@@ -89,8 +686,7 @@ impl Plugin for EditorPlugin {
             .add_systems(Update, game_state_adapter_system)
             .add_systems(Update, display_selected_sprite_sheet)
             .add_systems(Update, update_camera_transform)
-            .add_systems(Update, gizmos_hit_boxes_sprite)
-            .add_systems(Update, gizmos_hurt_boxes_sprite);
+            .add_systems(Update, gizmos_boxes_sprite);
     }
 }
 
@@ -99,8 +695,7 @@ fn load_sprite_sheets(
     mut texture_atlases: ResMut<Assets<TextureAtlasLayout>>,
     mut sprite_sheets: ResMut<EditorSpriteSheets>,
 ) {
-    let json_path = "assets/sprite_sheets.json";
-    let sprite_sheet_data: EditorSpriteSheetsData = load_settings_from_file(json_path);
+    let sprite_sheet_data: EditorSpriteSheetsData = load_settings_from_file(SPRITE_SHEETS_PATH);
 
     for info in sprite_sheet_data.sheets {
         let tex_handle = asset_server.load(&info.image_path);
@@ -114,10 +709,11 @@ fn load_sprite_sheets(
         let texture_atlas_layout_handle = texture_atlases.add(texture_atlas_layout);
         let atlas_data = EditorSpriteSheetAtlas {
             sprite_sheet_info: info.clone(),
+            saved_sprite_sheet_info: info.clone(),
             handle: texture_atlas_layout_handle,
-            sprite_sheet_path: info.image_path.clone(),
             texture_handle: tex_handle,
         };
+        sprite_sheets.order.push(info.id.clone());
         sprite_sheets.sheets.insert(info.id.clone(), atlas_data);
     }
 }
@@ -145,7 +741,7 @@ fn game_state_adapter_system(
             }
         }
 
-        GameMode::Game => {
+        GameMode::Game | GameMode::Replay | GameMode::Netplay | GameMode::Training => {
             if let Some(entity) = current_sprite_sheet_entity.entity.take() {
                 commands.entity(entity).despawn();
             }
@@ -159,7 +755,7 @@ fn game_state_adapter_system(
 fn display_selected_sprite_sheet(
     mut commands: Commands,
     mut sprite_sheets: ResMut<EditorSpriteSheets>,
-    mut selected_frame: ResMut<SelectedFrame>,
+    selected_frame: Res<SelectedFrame>,
     mut current_sprite_sheet: ResMut<EditorSpriteSheet>,
     game_state: Res<GameState>,
 ) {
@@ -176,31 +772,16 @@ fn display_selected_sprite_sheet(
         if let Some(frame_index) = &selected_frame.frame_index {
             if let Some(sprite_sheet_atlas) = sprite_sheets.sheets.get_mut(id) {
                 let texture_handle = sprite_sheet_atlas.texture_handle.clone();
-                let mut entity = commands.spawn(SpriteSheetBundle {
+                let entity = commands.spawn(SpriteSheetBundle {
                     texture: texture_handle,
                     atlas: TextureAtlas {
                         layout: sprite_sheet_atlas.handle.clone(),
                         index: *frame_index,
                     },
-                    transform: Transform::from_scale(Vec3::splat(6.0)),
+                    transform: editor_sprite_transform(),
                     ..default()
                 });
 
-                if let Some(frame_index) = selected_frame.frame_index {
-                    if let Some(sheet_info) = &mut selected_frame.sheet_info {
-                        if let Some(frame_data) = sheet_info.frames.get_mut(frame_index) {
-                            for hit_box in frame_data.hit_boxes.iter_mut() {
-                                println!("Hit box : {}", hit_box.size);
-                                entity.insert(hit_box.clone());
-                            }
-
-                            for hurt_box in frame_data.hurt_boxes.iter_mut() {
-                                println!("Hurt box : {}", hurt_box.size);
-                                entity.insert(hurt_box.clone());
-                            }
-                        }
-                    }
-                }
                 current_sprite_sheet.entity = Some(entity.id());
             }
         }
@@ -230,11 +811,12 @@ fn update_camera_transform(
             } else {
                 0.0
             };
+            let translation_y = -editor_space.bottom * 0.5;
 
             transform.translation = original_camera_transform.translation
                 + Vec3::new(
                 translation_x,
-                0.0,
+                translation_y,
                 0.0,
             );
         }
@@ -242,49 +824,29 @@ fn update_camera_transform(
 }
 
 
-fn gizmos_hurt_boxes_sprite(
-    mut gizmos: Gizmos,
-    query: Query<(&Transform, &EditorHurtBox)>,
-    game_state: Res<GameState>,
-) {
-    if game_state.mode != GameMode::Editor {
-        return;
-    }
-    for (transform, hurt_box) in query.iter() {
-        let scale = transform.scale.truncate();
-
-        let hurt_box_size_scaled = hurt_box.size * scale;
-        let hurt_box_offset_scaled = hurt_box.offset * scale;
-
-        gizmos.rect_2d(
-            transform.translation.truncate() + hurt_box_offset_scaled,
-            0.0,
-            hurt_box_size_scaled,
-            Color::GREEN,
-        );
-    }
-}
-
-fn gizmos_hit_boxes_sprite(
+fn gizmos_boxes_sprite(
     mut gizmos: Gizmos,
-    query: Query<(&Transform, &EditorHitBox)>,
+    selected_frame: Res<SelectedFrame>,
+    sprite_sheets: Res<EditorSpriteSheets>,
     game_state: Res<GameState>,
 ) {
     if game_state.mode != GameMode::Editor {
         return;
     }
-    for (transform, hit_box) in query.iter() {
-        let scale = transform.scale.truncate();
+    let Some(frame_data) = sprite_sheets.selected_frame_data(&selected_frame) else { return; };
 
-        let hit_box_size_scaled = hit_box.size * scale;
-        let hit_box_offset_scaled = hit_box.offset * scale;
+    let transform = editor_sprite_transform();
+    let scale = transform.scale.truncate();
 
-        gizmos.rect_2d(
-            transform.translation.truncate() + hit_box_offset_scaled,
-            0.0,
-            hit_box_size_scaled,
-            Color::RED,
-        );
+    for kind in EditorBoxKind::ALL {
+        for editor_box in frame_data.boxes(kind) {
+            gizmos.rect_2d(
+                transform.translation.truncate() + editor_box.offset * scale,
+                0.0,
+                editor_box.size * scale,
+                kind.color(),
+            );
+        }
     }
 }
 
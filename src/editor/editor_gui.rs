@@ -1,15 +1,32 @@
 use bevy::app::{App, Plugin};
+use bevy::window::WindowCloseRequested;
 use bevy_egui::{egui, EguiContexts, EguiPlugin};
 
 use crate::core::*;
-use crate::core::core_core::{GameMode, GameState};
+use crate::core::core_core::{apply_requested_mode, GameMode, GameState, ModeRequestSet};
 use crate::editor::editor_core::*;
+use crate::editor::history::history_core::{EditorCommand, EditorHistory};
 use crate::editor::inspector::inspector_core::*;
+use crate::editor::viewport::viewport_core::{BoxSelection, ViewportState};
 
 #[derive(Default, Resource)]
 pub struct EditorGuiSpace {
     pub left: f32,
     pub right: f32,
+    pub bottom: f32,
+}
+
+/// Pending action waiting for the user to decide what happens to unsaved sprite sheet edits.
+#[derive(Default, Resource)]
+struct UnsavedChangesPrompt {
+    requested_mode: Option<GameMode>,
+    closing_window: Option<Entity>,
+}
+
+impl UnsavedChangesPrompt {
+    fn is_open(&self) -> bool {
+        self.requested_mode.is_some() || self.closing_window.is_some()
+    }
 }
 
 pub struct EditorGuiPlugin;
@@ -19,15 +36,101 @@ impl Plugin for EditorGuiPlugin {
         app.add_plugins(InspectorPlugin)
             .add_plugins(EguiPlugin)
             .insert_resource(EditorGuiSpace::default())
-            .add_systems(Update, draw_selected_frame_details);
+            .insert_resource(UnsavedChangesPrompt::default())
+            .add_systems(Update, draw_selected_frame_details)
+            .add_systems(Update, guard_unsaved_changes.after(ModeRequestSet).before(apply_requested_mode))
+            .add_systems(Update, handle_window_close_requests)
+            .add_systems(Update, display_unsaved_changes_prompt.in_set(ModeRequestSet));
+    }
+}
+
+/// Holds back leaving the editor while sheets have unsaved edits.
+fn guard_unsaved_changes(
+    mut game_state: ResMut<GameState>,
+    mut prompt: ResMut<UnsavedChangesPrompt>,
+    sprite_sheets: Res<EditorSpriteSheets>) {
+    if game_state.mode != GameMode::Editor || !sprite_sheets.is_modified() {
+        return;
+    }
+
+    if let Some(mode) = game_state.requested_mode.take() {
+        if mode == GameMode::Editor {
+            return;
+        }
+        prompt.requested_mode = Some(mode);
+    }
+}
+
+fn handle_window_close_requests(
+    mut commands: Commands,
+    mut close_requests: EventReader<WindowCloseRequested>,
+    mut prompt: ResMut<UnsavedChangesPrompt>,
+    sprite_sheets: Res<EditorSpriteSheets>) {
+    for request in close_requests.read() {
+        if sprite_sheets.is_modified() {
+            prompt.closing_window = Some(request.window);
+        } else {
+            commands.entity(request.window).despawn();
+        }
+    }
+}
+
+fn display_unsaved_changes_prompt(
+    mut commands: Commands,
+    mut egui_contexts: EguiContexts,
+    mut prompt: ResMut<UnsavedChangesPrompt>,
+    mut game_state: ResMut<GameState>,
+    mut sprite_sheets: ResMut<EditorSpriteSheets>) {
+    if !prompt.is_open() {
+        return;
+    }
+    let ctx = egui_contexts.ctx_mut();
+
+    let mut proceed = None;
+    egui::Window::new("Unsaved Changes")
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+        .show(ctx, |ui| {
+            ui.label("The following sprite sheets have unsaved changes:");
+            for atlas in sprite_sheets.iter().filter(|atlas| atlas.is_modified()) {
+                ui.label(format!("- {}", atlas.sprite_sheet_info.id));
+            }
+            ui.horizontal(|ui| {
+                if ui.button("Save All").clicked() {
+                    proceed = Some(true);
+                }
+                if ui.button("Discard").clicked() {
+                    proceed = Some(false);
+                }
+                if ui.button("Cancel").clicked() {
+                    *prompt = UnsavedChangesPrompt::default();
+                }
+            });
+        });
+
+    let Some(save) = proceed else { return; };
+    if save {
+        sprite_sheets.save_all();
+    } else {
+        sprite_sheets.discard_all();
+    }
+
+    if let Some(mode) = prompt.requested_mode.take() {
+        game_state.requested_mode = Some(mode);
+    }
+    if let Some(window) = prompt.closing_window.take() {
+        commands.entity(window).despawn();
     }
 }
 
 fn draw_selected_frame_details(
     mut egui_contexts: EguiContexts,
     mut editor_space: ResMut<EditorGuiSpace>,
-    editor_sprite_sheets: ResMut<EditorSpriteSheets>,
-    mut selected_frame: ResMut<SelectedFrame>,
+    mut editor_sprite_sheets: ResMut<EditorSpriteSheets>,
+    selected_frame: Res<SelectedFrame>,
+    mut viewport: ResMut<ViewportState>,
+    mut history: ResMut<EditorHistory>,
     game_state: Res<GameState>) {
     if game_state.mode != GameMode::Editor {
         return;
@@ -39,41 +142,127 @@ fn draw_selected_frame_details(
         .default_width(editor_space.right)
         .show(ctx, |ui| {
             if let Some(frame_index) = selected_frame.frame_index {
-                if let Some(sheet_info) = &mut selected_frame.sheet_info {
+                if let Some(sheet_info) = selected_frame.sprite_sheet_id.as_ref()
+                    .and_then(|id| editor_sprite_sheets.sheet_info_mut(id)) {
+                    let suggested_phase = sheet_info.suggested_phases().get(frame_index).copied().unwrap_or_default();
                     if let Some(frame_data) = sheet_info.frames.get_mut(frame_index) {
-                        ui.collapsing("Hit Boxes", |ui| {
-                            for hit_box in frame_data.hit_boxes.iter_mut() {
-                                ui.horizontal(|ui| {
-                                    ui.label("Size");
-                                    ui.add(egui::DragValue::new(&mut hit_box.size.x));
-                                    ui.add(egui::DragValue::new(&mut hit_box.size.y));
+                        let frame_before = frame_data.clone();
+                        let mut edit_label = None;
+
+                        ui.horizontal(|ui| {
+                            ui.label("Draw");
+                            egui::ComboBox::from_id_source("draw_kind")
+                                .selected_text(viewport.draw_kind.label())
+                                .show_ui(ui, |ui| {
+                                    for kind in EditorBoxKind::ALL {
+                                        ui.selectable_value(&mut viewport.draw_kind, kind, kind.label());
+                                    }
                                 });
-                                ui.horizontal(|ui| {
-                                    ui.label("Offset");
-                                    ui.add(egui::DragValue::new(&mut hit_box.offset.x));
-                                    ui.add(egui::DragValue::new(&mut hit_box.offset.y));
+                        });
+
+                        ui.horizontal(|ui| {
+                            ui.label("Duration");
+                            let duration = egui::DragValue::new(&mut frame_data.duration)
+                                .clamp_range(1..=600)
+                                .suffix(" ticks");
+                            if ui.add(duration).on_hover_text("Frame duration at 60 ticks per second").changed() {
+                                edit_label = Some("Edit Frame Duration".to_string());
+                            }
+                        });
+
+                        ui.horizontal(|ui| {
+                            ui.label("Phase");
+                            egui::ComboBox::from_id_source("frame_phase")
+                                .selected_text(frame_data.phase.label())
+                                .show_ui(ui, |ui| {
+                                    for phase in EditorFramePhase::ALL {
+                                        if ui.selectable_value(&mut frame_data.phase, phase, phase.label()).changed() {
+                                            edit_label = Some("Edit Frame Phase".to_string());
+                                        }
+                                    }
                                 });
+                            if suggested_phase != frame_data.phase {
+                                ui.weak(format!("suggested: {}", suggested_phase.label()));
                             }
                         });
 
-                        ui.collapsing("Hurt Boxes", |ui| {
-                            for hurt_box in frame_data.hurt_boxes.iter_mut() {
-                                ui.horizontal(|ui| {
-                                    ui.label("Size");
-                                    ui.add(egui::DragValue::new(&mut hurt_box.size.x));
-                                    ui.add(egui::DragValue::new(&mut hurt_box.size.y));
+                        for kind in EditorBoxKind::ALL {
+                            let header = egui::RichText::new(format!("{}es ({})", kind.label(), frame_data.boxes(kind).len()))
+                                .color(egui_color(kind.color()));
+                            egui::CollapsingHeader::new(header).id_source(kind.label()).show(ui, |ui| {
+                                edit_label = draw_box_list(ui, frame_data, kind, &mut viewport).or(edit_label.take());
+                            });
+                        }
+
+                        if let Some(label) = edit_label {
+                            history.record(label, EditorCommand::EditFrame {
+                                sheet_id: sheet_info.id.clone(),
+                                frame_index,
+                                before: frame_before,
+                                after: frame_data.clone(),
+                            });
+                        }
+
+                        if ui.button("Remove Empty Boxes").on_hover_text("Removes the zero-sized boxes of every frame in this sheet").clicked() {
+                            let sheet_before = sheet_info.clone();
+                            for frame_data in sheet_info.frames.iter_mut() {
+                                frame_data.remove_empty_boxes();
+                            }
+                            history.record("Remove Empty Boxes", EditorCommand::EditSheet {
+                                before: sheet_before,
+                                after: sheet_info.clone(),
+                            });
+                            viewport.selected_box = None;
+                        }
+
+                        ui.collapsing("Frame Data", |ui| {
+                            let summary = sheet_info.phase_summary();
+                            egui::Grid::new("phase_summary").show(ui, |ui| {
+                                for (phase, ticks) in [
+                                    (EditorFramePhase::Startup, summary.startup),
+                                    (EditorFramePhase::Active, summary.active),
+                                    (EditorFramePhase::Recovery, summary.recovery),
+                                ] {
+                                    ui.colored_label(egui_color(phase.color()), phase.label());
+                                    ui.label(ticks.to_string());
+                                    ui.end_row();
+                                }
+                                ui.label("Total");
+                                ui.label(summary.total().to_string());
+                                ui.end_row();
+                            });
+
+                            let suggested_phases = sheet_info.suggested_phases();
+                            let differs = sheet_info.frames.iter().zip(&suggested_phases).any(|(frame, phase)| frame.phase != *phase);
+                            let apply = ui.add_enabled(differs, egui::Button::new("Apply Suggested Phases"))
+                                .on_hover_text("Frames with hit boxes become active, frames before them startup and frames after them recovery");
+                            if apply.clicked() {
+                                let sheet_before = sheet_info.clone();
+                                for (frame, phase) in sheet_info.frames.iter_mut().zip(suggested_phases) {
+                                    frame.phase = phase;
+                                }
+                                history.record("Apply Suggested Phases", EditorCommand::EditSheet {
+                                    before: sheet_before,
+                                    after: sheet_info.clone(),
                                 });
-                                ui.horizontal(|ui| {
-                                    ui.label("Offset");
-                                    ui.add(egui::DragValue::new(&mut hurt_box.offset.x));
-                                    ui.add(egui::DragValue::new(&mut hurt_box.offset.y));
+                            }
+                        });
+
+                        ui.collapsing("Clips", |ui| {
+                            // Only the clips change here, the rest of the sheet is copied once an edit happened
+                            let clips_before = sheet_info.clips.clone();
+                            if draw_clip_list(ui, sheet_info) {
+                                let sheet_before = EditorSpriteSheetInfo { clips: clips_before, ..sheet_info.clone() };
+                                history.record("Edit Clips", EditorCommand::EditSheet {
+                                    before: sheet_before,
+                                    after: sheet_info.clone(),
                                 });
                             }
                         });
 
-                        if ui.button("Save").clicked() {
-                            let data_to_save = prepare_sprite_sheets_for_saving(&selected_frame, editor_sprite_sheets);
-                            save_settings_to_file("assets/sprite_sheets.json", &data_to_save);
+                        let sheet_id = sheet_info.id.clone();
+                        if ui.button("Save").on_hover_text("Saves the changes made to this sheet").clicked() {
+                            editor_sprite_sheets.save(&[sheet_id]);
                         }
                     }
                 }
@@ -84,20 +273,196 @@ fn draw_selected_frame_details(
         .width();
 }
 
-fn prepare_sprite_sheets_for_saving(
-    selected_frame: &SelectedFrame,
-    mut sprite_sheets: ResMut<EditorSpriteSheets>) -> EditorSpriteSheetsData {
-    if let Some(sheet_info) = &selected_frame.sheet_info {
-        if let Some(atlas) = sprite_sheets.sheets.get_mut(&sheet_info.id) {
-            atlas.sprite_sheet_info = sheet_info.clone();
+pub fn egui_color(color: Color) -> egui::Color32 {
+    let [r, g, b, a] = color.as_rgba_u8();
+    egui::Color32::from_rgba_unmultiplied(r, g, b, a)
+}
+
+/// Draws the editable list of named clips of the sheet, returns whether anything changed.
+fn draw_clip_list(ui: &mut egui::Ui, sheet_info: &mut EditorSpriteSheetInfo) -> bool {
+    let last_frame = sheet_info.frames.len().saturating_sub(1);
+    let mut changed = false;
+    let mut removed = None;
+
+    for (index, clip) in sheet_info.clips.iter_mut().enumerate() {
+        ui.push_id(index, |ui| {
+            ui.horizontal(|ui| {
+                changed |= ui.add(egui::TextEdit::singleline(&mut clip.name).desired_width(120.0)).changed();
+                if ui.small_button("Remove").clicked() {
+                    removed = Some(index);
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label("Frames");
+                changed |= ui.add(egui::DragValue::new(&mut clip.start).clamp_range(0..=last_frame)).changed();
+                changed |= ui.add(egui::DragValue::new(&mut clip.end).clamp_range(0..=last_frame)).changed();
+                clip.end = clip.end.max(clip.start);
+
+                egui::ComboBox::from_id_source("loop_mode")
+                    .selected_text(clip.loop_mode.label())
+                    .show_ui(ui, |ui| {
+                        for loop_mode in EditorClipLoopMode::ALL {
+                            changed |= ui.selectable_value(&mut clip.loop_mode, loop_mode, loop_mode.label()).changed();
+                        }
+                    });
+            });
+            ui.separator();
+        });
+    }
+
+    if let Some(index) = removed {
+        sheet_info.clips.remove(index);
+        changed = true;
+    }
+
+    if ui.button("Add Clip").clicked() {
+        let clip = EditorAnimationClip {
+            name: format!("clip-{}", sheet_info.clips.len()),
+            ..sheet_info.whole_sheet_clip(EditorClipLoopMode::Once)
+        };
+        sheet_info.clips.push(clip);
+        changed = true;
+    }
+
+    changed
+}
+
+/// Draws the editable hit properties of a hit box, returns whether anything changed.
+fn draw_hit_properties(ui: &mut egui::Ui, properties: &mut EditorHitProperties) -> bool {
+    let mut changed = false;
+
+    egui::Grid::new("hit_properties").show(ui, |ui| {
+        for (label, value) in [
+            ("Damage", &mut properties.damage),
+            ("Chip Damage", &mut properties.chip_damage),
+            ("Hitstun", &mut properties.hitstun),
+            ("Blockstun", &mut properties.blockstun),
+            ("Hitstop", &mut properties.hitstop),
+            ("Hit Group", &mut properties.hit_group),
+        ] {
+            ui.label(label);
+            changed |= ui.add(egui::DragValue::new(value)).changed();
+            ui.end_row();
+        }
+
+        ui.label("Knockback");
+        ui.horizontal(|ui| {
+            changed |= ui.add(egui::DragValue::new(&mut properties.knockback.x).speed(0.1)).changed();
+            changed |= ui.add(egui::DragValue::new(&mut properties.knockback.y).speed(0.1)).changed();
+        });
+        ui.end_row();
+
+        ui.label("Hit Level");
+        egui::ComboBox::from_id_source("hit_level")
+            .selected_text(properties.hit_level.label())
+            .show_ui(ui, |ui| {
+                for hit_level in EditorHitLevel::ALL {
+                    changed |= ui.selectable_value(&mut properties.hit_level, hit_level, hit_level.label()).changed();
+                }
+            });
+        ui.end_row();
+
+        ui.label("Bounces");
+        ui.horizontal(|ui| {
+            changed |= ui.checkbox(&mut properties.ground_bounce, "Ground").changed();
+            changed |= ui.checkbox(&mut properties.wall_bounce, "Wall").changed();
+        });
+        ui.end_row();
+    });
+
+    changed
+}
+
+enum BoxListAction {
+    Duplicate(usize),
+    MoveUp(usize),
+    MoveDown(usize),
+    Remove(usize),
+    Add,
+}
+
+/// Draws the editable list of boxes of one kind, returns the history label of the edit made, if any.
+fn draw_box_list(
+    ui: &mut egui::Ui,
+    frame_data: &mut EditorFrameData,
+    kind: EditorBoxKind,
+    viewport: &mut ViewportState) -> Option<String> {
+    let count = frame_data.boxes(kind).len();
+    let mut action = None;
+    let mut edited = None;
+
+    for (index, editor_box) in frame_data.boxes_mut(kind).iter_mut().enumerate() {
+        let selection = BoxSelection { kind, index };
+
+        ui.horizontal(|ui| {
+            let is_selected = viewport.selected_box == Some(selection);
+            if ui.selectable_label(is_selected, format!("{} {}", kind.label(), index)).clicked() {
+                viewport.selected_box = Some(selection);
+            }
+            if ui.small_button("Duplicate").clicked() {
+                action = Some(BoxListAction::Duplicate(index));
+            }
+            if ui.add_enabled(index > 0, egui::Button::new("Up").small()).clicked() {
+                action = Some(BoxListAction::MoveUp(index));
+            }
+            if ui.add_enabled(index + 1 < count, egui::Button::new("Down").small()).clicked() {
+                action = Some(BoxListAction::MoveDown(index));
+            }
+            if ui.small_button("Remove").clicked() {
+                action = Some(BoxListAction::Remove(index));
+            }
+        });
+        let changed = ui.horizontal(|ui| {
+            ui.label("Size");
+            ui.add(egui::DragValue::new(&mut editor_box.size.x)).changed()
+                | ui.add(egui::DragValue::new(&mut editor_box.size.y)).changed()
+        }).inner | ui.horizontal(|ui| {
+            ui.label("Offset");
+            ui.add(egui::DragValue::new(&mut editor_box.offset.x)).changed()
+                | ui.add(egui::DragValue::new(&mut editor_box.offset.y)).changed()
+        }).inner;
+        if kind.has_hit_properties() {
+            egui::CollapsingHeader::new("Hit Properties").id_source((kind.label(), index)).show(ui, |ui| {
+                let mut properties = editor_box.hit_properties();
+                if draw_hit_properties(ui, &mut properties) {
+                    editor_box.properties = Some(properties);
+                    edited = Some(format!("Edit {} {} Properties", kind.label(), index));
+                }
+            });
         }
+        if changed {
+            edited = Some(format!("Edit {} {}", kind.label(), index));
+        }
+        ui.separator();
     }
 
-    let sheets: Vec<EditorSpriteSheetInfo> =
-        sprite_sheets.sheets.values()
-            .map(|atlas|
-                atlas.sprite_sheet_info.clone())
-            .collect();
+    if ui.button(format!("Add {}", kind.label())).clicked() {
+        action = Some(BoxListAction::Add);
+    }
 
-    EditorSpriteSheetsData { sheets }
+    let Some(action) = action else { return edited; };
+    let (selected, label) = match action {
+        BoxListAction::Duplicate(index) => (
+            frame_data.duplicate_box(kind, index),
+            format!("Duplicate {} {}", kind.label(), index),
+        ),
+        BoxListAction::MoveUp(index) => {
+            frame_data.swap_boxes(kind, index, index - 1);
+            (Some(index - 1), format!("Move {} {} Up", kind.label(), index))
+        }
+        BoxListAction::MoveDown(index) => {
+            frame_data.swap_boxes(kind, index, index + 1);
+            (Some(index + 1), format!("Move {} {} Down", kind.label(), index))
+        }
+        BoxListAction::Remove(index) => {
+            frame_data.remove_box(kind, index);
+            (None, format!("Remove {} {}", kind.label(), index))
+        }
+        BoxListAction::Add => (
+            Some(frame_data.push_box(kind, Vec2::splat(10.0), Vec2::ZERO)),
+            format!("Add {}", kind.label()),
+        ),
+    };
+    viewport.selected_box = selected.map(|index| BoxSelection { kind, index });
+    Some(label)
 }
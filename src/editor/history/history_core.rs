@@ -0,0 +1,280 @@
+use crate::core::core_core::{GameMode, GameState};
+use crate::editor::history::*;
+use crate::editor::inspector::inspector_core::SelectedFrame;
+use crate::editor::viewport::viewport_core::ViewportState;
+
+pub(crate) struct HistoryPlugin;
+
+impl Plugin for HistoryPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(EditorHistory::default())
+            .add_systems(Update, history_shortcuts_system)
+            .add_systems(Update, close_history_merge)
+            .add_systems(Update, display_history);
+    }
+}
+
+/// A reversible edit of the sprite sheet data, stored as the state before and after the edit.
+#[derive(Clone, Debug)]
+pub enum EditorCommand {
+    EditFrame {
+        sheet_id: String,
+        frame_index: usize,
+        before: EditorFrameData,
+        after: EditorFrameData,
+    },
+    EditSheet {
+        before: EditorSpriteSheetInfo,
+        after: EditorSpriteSheetInfo,
+    },
+}
+
+impl EditorCommand {
+    pub fn sheet_id(&self) -> &str {
+        match self {
+            EditorCommand::EditFrame { sheet_id, .. } => sheet_id,
+            EditorCommand::EditSheet { before, .. } => &before.id,
+        }
+    }
+
+    fn frame_index(&self) -> Option<usize> {
+        match self {
+            EditorCommand::EditFrame { frame_index, .. } => Some(*frame_index),
+            EditorCommand::EditSheet { .. } => None,
+        }
+    }
+
+    fn is_noop(&self) -> bool {
+        match self {
+            EditorCommand::EditFrame { before, after, .. } => before == after,
+            EditorCommand::EditSheet { before, after } => before == after,
+        }
+    }
+
+    /// Takes the `after` state of `next`, used to fold a drag into a single command.
+    fn merge(&mut self, next: EditorCommand) {
+        match (self, next) {
+            (EditorCommand::EditFrame { after, .. }, EditorCommand::EditFrame { after: next_after, .. }) => {
+                *after = next_after;
+            }
+            (EditorCommand::EditSheet { after, .. }, EditorCommand::EditSheet { after: next_after, .. }) => {
+                *after = next_after;
+            }
+            _ => {}
+        }
+    }
+
+    fn apply(&self, sheet_info: &mut EditorSpriteSheetInfo, undo: bool) {
+        match self {
+            EditorCommand::EditFrame { frame_index, before, after, .. } => {
+                if let Some(frame_data) = sheet_info.frames.get_mut(*frame_index) {
+                    *frame_data = if undo { before.clone() } else { after.clone() };
+                }
+            }
+            EditorCommand::EditSheet { before, after } => {
+                *sheet_info = if undo { before.clone() } else { after.clone() };
+            }
+        }
+    }
+}
+
+pub struct EditorHistoryEntry {
+    pub label: String,
+    pub command: EditorCommand,
+}
+
+/// Undo/redo stack of the editor, entries before `cursor` are applied.
+#[derive(Default, Resource)]
+pub struct EditorHistory {
+    entries: Vec<EditorHistoryEntry>,
+    cursor: usize,
+    /// While set, a command with the same label and target replaces the `after` state of the last entry.
+    merge_open: bool,
+}
+
+impl EditorHistory {
+    /// Records an edit that has already been applied to the sprite sheet data.
+    pub fn record(&mut self, label: impl Into<String>, command: EditorCommand) {
+        let label = label.into();
+
+        if self.merge_open {
+            if let Some(last) = self.entries.last_mut() {
+                if last.label == label
+                    && last.command.sheet_id() == command.sheet_id()
+                    && last.command.frame_index() == command.frame_index() {
+                    last.command.merge(command);
+                    if last.command.is_noop() {
+                        self.entries.pop();
+                        self.cursor = self.entries.len();
+                        self.merge_open = false;
+                    }
+                    return;
+                }
+            }
+        }
+
+        if command.is_noop() {
+            return;
+        }
+
+        self.entries.truncate(self.cursor);
+        self.entries.push(EditorHistoryEntry { label, command });
+        self.cursor = self.entries.len();
+        self.merge_open = true;
+    }
+
+    pub fn entries(&self) -> &[EditorHistoryEntry] {
+        &self.entries
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.cursor > 0
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.cursor < self.entries.len()
+    }
+
+    fn undo(&mut self) -> Option<&EditorCommand> {
+        if !self.can_undo() {
+            return None;
+        }
+        self.merge_open = false;
+        self.cursor -= 1;
+        Some(&self.entries[self.cursor].command)
+    }
+
+    fn redo(&mut self) -> Option<&EditorCommand> {
+        if !self.can_redo() {
+            return None;
+        }
+        self.merge_open = false;
+        self.cursor += 1;
+        Some(&self.entries[self.cursor - 1].command)
+    }
+}
+
+fn step_history(
+    history: &mut EditorHistory,
+    selected_frame: &mut SelectedFrame,
+    sprite_sheets: &mut EditorSpriteSheets,
+    viewport: &mut ViewportState,
+    undo: bool) {
+    let command = if undo { history.undo() } else { history.redo() };
+    let Some(command) = command else { return; };
+
+    if let Some(sheet_info) = sprite_sheets.sheet_info_mut(command.sheet_id()) {
+        command.apply(sheet_info, undo);
+    }
+
+    // Bring the edited frame into view when it belongs to the sheet being edited.
+    if selected_frame.sprite_sheet_id.as_deref() == Some(command.sheet_id()) {
+        if let Some(frame_index) = command.frame_index() {
+            selected_frame.frame_index = Some(frame_index);
+        }
+    }
+    viewport.selected_box = None;
+}
+
+fn history_shortcuts_system(
+    mut egui_contexts: EguiContexts,
+    mut history: ResMut<EditorHistory>,
+    mut selected_frame: ResMut<SelectedFrame>,
+    mut sprite_sheets: ResMut<EditorSpriteSheets>,
+    mut viewport: ResMut<ViewportState>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    game_state: Res<GameState>,
+) {
+    if game_state.mode != GameMode::Editor {
+        return;
+    }
+    if egui_contexts.ctx_mut().wants_keyboard_input() {
+        return;
+    }
+
+    let control = keyboard.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+    let shift = keyboard.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    if !control {
+        return;
+    }
+
+    if keyboard.just_pressed(KeyCode::KeyZ) {
+        step_history(&mut history, &mut selected_frame, &mut sprite_sheets, &mut viewport, !shift);
+    } else if keyboard.just_pressed(KeyCode::KeyY) {
+        step_history(&mut history, &mut selected_frame, &mut sprite_sheets, &mut viewport, false);
+    }
+}
+
+/// Ends merging once the mouse is released and no text is being typed,
+/// so each drag or text edit becomes a single entry.
+fn close_history_merge(
+    mut egui_contexts: EguiContexts,
+    mut history: ResMut<EditorHistory>,
+    mouse: Res<ButtonInput<MouseButton>>,
+) {
+    if history.merge_open
+        && !mouse.pressed(MouseButton::Left)
+        && !egui_contexts.ctx_mut().wants_keyboard_input() {
+        history.merge_open = false;
+    }
+}
+
+fn display_history(
+    mut egui_contexts: EguiContexts,
+    mut history: ResMut<EditorHistory>,
+    mut selected_frame: ResMut<SelectedFrame>,
+    mut sprite_sheets: ResMut<EditorSpriteSheets>,
+    mut viewport: ResMut<ViewportState>,
+    game_state: Res<GameState>,
+) {
+    if game_state.mode != GameMode::Editor {
+        return;
+    }
+    let ctx = egui_contexts.ctx_mut();
+
+    let mut target_cursor = None;
+
+    egui::Window::new("History")
+        .default_open(false)
+        .show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                if ui.add_enabled(history.can_undo(), egui::Button::new("Undo")).clicked() {
+                    target_cursor = Some(history.cursor() - 1);
+                }
+                if ui.add_enabled(history.can_redo(), egui::Button::new("Redo")).clicked() {
+                    target_cursor = Some(history.cursor() + 1);
+                }
+            });
+            ui.separator();
+
+            egui::ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                if ui.selectable_label(history.cursor() == 0, "Initial State").clicked() {
+                    target_cursor = Some(0);
+                }
+                for (index, entry) in history.entries().iter().enumerate() {
+                    let label = format!("{} ({})", entry.label, entry.command.sheet_id());
+                    let text = if index < history.cursor() {
+                        egui::RichText::new(label)
+                    } else {
+                        egui::RichText::new(label).weak()
+                    };
+                    if ui.selectable_label(history.cursor() == index + 1, text).clicked() {
+                        target_cursor = Some(index + 1);
+                    }
+                }
+            });
+        });
+
+    if let Some(target_cursor) = target_cursor {
+        while history.cursor() > target_cursor {
+            step_history(&mut history, &mut selected_frame, &mut sprite_sheets, &mut viewport, true);
+        }
+        while history.cursor() < target_cursor {
+            step_history(&mut history, &mut selected_frame, &mut sprite_sheets, &mut viewport, false);
+        }
+    }
+}
@@ -0,0 +1,6 @@
+pub mod history_core;
+
+use crate::editor::editor_core::*;
+
+use bevy::prelude::*;
+use bevy_egui::*;
@@ -14,14 +14,13 @@ impl Plugin for InspectorPlugin {
 pub struct SelectedFrame {
     pub sprite_sheet_id: Option<String>,
     pub frame_index: Option<usize>,
-    pub sheet_info: Option<EditorSpriteSheetInfo>,
 }
 
 fn inspector_setup(
     mut egui_contexts: EguiContexts,
     mut editor_space: ResMut<EditorGuiSpace>,
     mut selected_frame: ResMut<SelectedFrame>,
-    editor_sprite_sheets: Res<EditorSpriteSheets>,
+    mut editor_sprite_sheets: ResMut<EditorSpriteSheets>,
     game_state: Res<GameState>
 ) {
     if game_state.mode != GameMode::Editor {
@@ -33,12 +32,20 @@ fn inspector_setup(
         .resizable(true)
         .default_width(editor_space.left)
         .show(ctx, |ui| {
+            if ui.add_enabled(editor_sprite_sheets.is_modified(), egui::Button::new("Save All")).clicked() {
+                editor_sprite_sheets.save_all();
+            }
+
             ui.collapsing("Sprite Sheets", |ui| {
                 egui::ScrollArea::vertical().show(ui, |ui| {
-                    for (_sheet_name, sheet_atlas) in editor_sprite_sheets.sheets.iter() {
+                    for sheet_atlas in editor_sprite_sheets.iter() {
                         let sheet_info = &sheet_atlas.sprite_sheet_info;
-                        let sheet_info_label = format!("{}", sheet_info.id);
-                        ui.collapsing(sheet_info_label, |ui| {
+                        let sheet_info_label = if sheet_atlas.is_modified() {
+                            format!("{} (modified)", sheet_info.id)
+                        } else {
+                            sheet_info.id.clone()
+                        };
+                        egui::CollapsingHeader::new(sheet_info_label).id_source(&sheet_info.id).show(ui, |ui| {
                             ui.collapsing("Frames", |ui| {
                                 for (frame_index, _frame) in sheet_info.frames.iter().enumerate() {
                                     let frame_label = format!("Frame: {}", frame_index);
@@ -49,7 +56,6 @@ fn inspector_setup(
                                     if ui.selectable_label(is_selected, frame_label).clicked() {
                                         selected_frame.sprite_sheet_id = Some(sheet_info.id.clone());
                                         selected_frame.frame_index = Some(frame_index);
-                                        selected_frame.sheet_info = Some(sheet_info.clone());
                                     }
                                 }
                             });
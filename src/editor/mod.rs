@@ -1,5 +1,8 @@
 pub mod editor_core;
 pub mod editor_gui;
+pub mod history;
 pub mod inspector;
+pub mod timeline;
+pub mod viewport;
 
 use bevy::prelude::*;
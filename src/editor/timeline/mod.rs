@@ -0,0 +1,7 @@
+pub mod timeline_core;
+
+use crate::editor::editor_core::*;
+use crate::editor::editor_gui::*;
+
+use bevy::prelude::*;
+use bevy_egui::*;
@@ -0,0 +1,288 @@
+use crate::core::core_core::{GameMode, GameState};
+use crate::editor::inspector::inspector_core::SelectedFrame;
+use crate::editor::timeline::*;
+
+/// Seconds per animation tick, frame durations are counted in these.
+const TICK_SECONDS: f32 = 1.0 / 60.0;
+
+const THUMBNAIL_SIZE: f32 = 64.0;
+
+pub(crate) struct TimelinePlugin;
+
+impl Plugin for TimelinePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(TimelineState::default())
+            .add_systems(Update, timeline_shortcuts_system)
+            .add_systems(Update, timeline_playback_system)
+            .add_systems(Update, display_timeline);
+    }
+}
+
+#[derive(Resource)]
+pub struct TimelineState {
+    pub playing: bool,
+    /// Applies when playing all frames, named clips use their own loop mode.
+    pub looping: bool,
+    pub speed: f32,
+    /// Index of the named clip to play, `None` plays every frame of the sheet.
+    pub clip: Option<usize>,
+    playback: Option<ClipPlayback>,
+    /// Time not yet consumed by a tick, in seconds.
+    elapsed: f32,
+}
+
+impl Default for TimelineState {
+    fn default() -> Self {
+        TimelineState {
+            playing: false,
+            looping: true,
+            speed: 1.0,
+            clip: None,
+            playback: None,
+            elapsed: 0.0,
+        }
+    }
+}
+
+impl TimelineState {
+    fn clip(&self, sheet_info: &EditorSpriteSheetInfo) -> EditorAnimationClip {
+        match self.clip.and_then(|index| sheet_info.clips.get(index)) {
+            Some(clip) => clip.clone(),
+            None => {
+                let loop_mode = if self.looping { EditorClipLoopMode::Loop } else { EditorClipLoopMode::Once };
+                sheet_info.whole_sheet_clip(loop_mode)
+            }
+        }
+    }
+
+    fn toggle_playing(&mut self, selected_frame: &SelectedFrame, sprite_sheets: &EditorSpriteSheets) {
+        self.playing = !self.playing;
+        self.elapsed = 0.0;
+        self.playback = None;
+
+        if self.playing {
+            let sheet_info = selected_frame.sprite_sheet_id.as_ref().and_then(|id| sprite_sheets.sheet_info(id));
+            if let (Some(sheet_info), Some(frame_index)) = (sheet_info, selected_frame.frame_index) {
+                self.playback = Some(ClipPlayback::starting_at(self.clip(sheet_info), frame_index));
+            }
+        }
+    }
+
+    fn stop(&mut self) {
+        self.playing = false;
+        self.playback = None;
+    }
+}
+
+fn step_frame(selected_frame: &mut SelectedFrame, frame_count: usize, forward: bool, looping: bool) -> bool {
+    let Some(frame_index) = selected_frame.frame_index else { return false; };
+    if frame_count == 0 {
+        return false;
+    }
+
+    let next = if forward {
+        if frame_index + 1 < frame_count {
+            frame_index + 1
+        } else if looping {
+            0
+        } else {
+            return false;
+        }
+    } else if frame_index > 0 {
+        frame_index - 1
+    } else if looping {
+        frame_count - 1
+    } else {
+        return false;
+    };
+
+    selected_frame.frame_index = Some(next);
+    true
+}
+
+fn selected_frame_count(selected_frame: &SelectedFrame, sprite_sheets: &EditorSpriteSheets) -> usize {
+    selected_frame.sprite_sheet_id.as_ref()
+        .and_then(|id| sprite_sheets.sheet_info(id))
+        .map_or(0, |sheet_info| sheet_info.frames.len())
+}
+
+fn timeline_playback_system(
+    time: Res<Time>,
+    mut timeline: ResMut<TimelineState>,
+    mut selected_frame: ResMut<SelectedFrame>,
+    sprite_sheets: Res<EditorSpriteSheets>,
+    game_state: Res<GameState>,
+) {
+    if game_state.mode != GameMode::Editor || !timeline.playing {
+        return;
+    }
+
+    let sheet_info = selected_frame.sprite_sheet_id.as_ref().and_then(|id| sprite_sheets.sheet_info(id));
+    let (Some(sheet_info), Some(mut playback)) = (sheet_info, timeline.playback.take()) else {
+        timeline.stop();
+        return;
+    };
+
+    timeline.elapsed += time.delta_seconds() * timeline.speed;
+    while timeline.elapsed >= TICK_SECONDS {
+        timeline.elapsed -= TICK_SECONDS;
+        playback.tick(sheet_info);
+    }
+
+    selected_frame.frame_index = Some(playback.frame);
+    if playback.finished {
+        timeline.stop();
+    } else {
+        timeline.playback = Some(playback);
+    }
+}
+
+fn timeline_shortcuts_system(
+    mut egui_contexts: EguiContexts,
+    mut timeline: ResMut<TimelineState>,
+    mut selected_frame: ResMut<SelectedFrame>,
+    sprite_sheets: Res<EditorSpriteSheets>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    game_state: Res<GameState>,
+) {
+    if game_state.mode != GameMode::Editor {
+        return;
+    }
+    if egui_contexts.ctx_mut().wants_keyboard_input() {
+        return;
+    }
+
+    let frame_count = selected_frame_count(&selected_frame, &sprite_sheets);
+    if keyboard.just_pressed(KeyCode::Space) {
+        timeline.toggle_playing(&selected_frame, &sprite_sheets);
+    } else if keyboard.just_pressed(KeyCode::ArrowLeft) {
+        timeline.stop();
+        step_frame(&mut selected_frame, frame_count, false, true);
+    } else if keyboard.just_pressed(KeyCode::ArrowRight) {
+        timeline.stop();
+        step_frame(&mut selected_frame, frame_count, true, true);
+    }
+}
+
+fn display_timeline(
+    mut egui_contexts: EguiContexts,
+    mut editor_space: ResMut<EditorGuiSpace>,
+    mut timeline: ResMut<TimelineState>,
+    mut selected_frame: ResMut<SelectedFrame>,
+    sprite_sheets: Res<EditorSpriteSheets>,
+    game_state: Res<GameState>,
+) {
+    if game_state.mode != GameMode::Editor {
+        return;
+    }
+
+    let Some(atlas) = selected_frame.sprite_sheet_id.as_ref().and_then(|id| sprite_sheets.sheets.get(id)) else {
+        editor_space.bottom = 0.0;
+        return;
+    };
+    let texture_id = egui_contexts.add_image(atlas.texture_handle.clone_weak());
+    let sheet_info = &atlas.sprite_sheet_info;
+    let frame_count = sheet_info.frames.len();
+    let ctx = egui_contexts.ctx_mut();
+
+    editor_space.bottom = egui::TopBottomPanel::bottom("Timeline")
+        .resizable(false)
+        .show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                if ui.button("|<").on_hover_text("Previous frame").clicked() {
+                    timeline.stop();
+                    step_frame(&mut selected_frame, frame_count, false, true);
+                }
+                let play_text = if timeline.playing { "Pause" } else { "Play" };
+                if ui.button(play_text).clicked() {
+                    timeline.toggle_playing(&selected_frame, &sprite_sheets);
+                }
+                if ui.button(">|").on_hover_text("Next frame").clicked() {
+                    timeline.stop();
+                    step_frame(&mut selected_frame, frame_count, true, true);
+                }
+
+                let clip_name = timeline.clip
+                    .and_then(|index| sheet_info.clips.get(index))
+                    .map_or("All Frames", |clip| clip.name.as_str());
+                egui::ComboBox::from_id_source("timeline_clip")
+                    .selected_text(clip_name)
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut timeline.clip, None, "All Frames");
+                        for (index, clip) in sheet_info.clips.iter().enumerate() {
+                            ui.selectable_value(&mut timeline.clip, Some(index), &clip.name);
+                        }
+                    });
+                ui.add_enabled(timeline.clip.is_none(), egui::Checkbox::new(&mut timeline.looping, "Loop"));
+                ui.add(egui::Slider::new(&mut timeline.speed, 0.1..=2.0).text("Speed"));
+
+                if let Some(frame_index) = selected_frame.frame_index.as_mut() {
+                    if frame_count > 0 {
+                        ui.add(egui::Slider::new(frame_index, 0..=frame_count - 1).text("Frame"));
+                    }
+                }
+            });
+
+            egui::ScrollArea::horizontal().show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    for (frame_index, frame_data) in sheet_info.frames.iter().enumerate() {
+                        let is_selected = selected_frame.frame_index == Some(frame_index);
+                        let response = ui.vertical(|ui| {
+                            let response = draw_frame_thumbnail(ui, texture_id, sheet_info, frame_index, frame_data, is_selected);
+                            draw_phase_bar(ui, frame_data.phase, response.rect.width());
+                            ui.label(format!("{}: {}t", frame_index, frame_data.duration))
+                                .on_hover_text("Frame index and duration in ticks");
+                            response
+                        }).inner;
+
+                        if response.clicked() {
+                            timeline.stop();
+                            selected_frame.frame_index = Some(frame_index);
+                        }
+                    }
+                });
+            });
+        })
+        .response
+        .rect
+        .height();
+}
+
+fn draw_phase_bar(ui: &mut egui::Ui, phase: EditorFramePhase, width: f32) {
+    let (rect, response) = ui.allocate_exact_size(egui::vec2(width, 4.0), egui::Sense::hover());
+    ui.painter().rect_filled(rect, 0.0, egui_color(phase.color()));
+    response.on_hover_text(phase.label());
+}
+
+/// Draws one frame of the sheet with its boxes on top.
+fn draw_frame_thumbnail(
+    ui: &mut egui::Ui,
+    texture_id: egui::TextureId,
+    sheet_info: &EditorSpriteSheetInfo,
+    frame_index: usize,
+    frame_data: &EditorFrameData,
+    is_selected: bool) -> egui::Response {
+    let columns = sheet_info.columns.max(1);
+    let (column, row) = ((frame_index % columns) as f32, (frame_index / columns) as f32);
+    let sheet_size = egui::vec2(sheet_info.sprite_sheet_width as f32, sheet_info.sprite_sheet_height as f32);
+    let tile_size = egui::vec2(sheet_info.tile_width as f32, sheet_info.tile_height as f32);
+    let uv_min = egui::pos2(column * tile_size.x / sheet_size.x, row * tile_size.y / sheet_size.y);
+    let uv = egui::Rect::from_min_size(uv_min, tile_size / sheet_size);
+
+    let image = egui::Image::new((texture_id, egui::Vec2::splat(THUMBNAIL_SIZE))).uv(uv);
+    let response = ui.add(egui::ImageButton::new(image).selected(is_selected));
+
+    // Box offsets are in sprite pixels from the tile center with y pointing up.
+    let scale = THUMBNAIL_SIZE / tile_size.x.max(tile_size.y);
+    let center = response.rect.center();
+    let painter = ui.painter_at(response.rect);
+    for kind in EditorBoxKind::ALL {
+        for editor_box in frame_data.boxes(kind) {
+            let box_center = center + egui::vec2(editor_box.offset.x, -editor_box.offset.y) * scale;
+            let rect = egui::Rect::from_center_size(box_center, egui::vec2(editor_box.size.x, editor_box.size.y) * scale);
+            painter.rect_stroke(rect, 0.0, egui::Stroke::new(1.0, egui_color(kind.color())));
+        }
+    }
+
+    response
+}
@@ -0,0 +1,6 @@
+pub mod viewport_core;
+
+use crate::editor::editor_core::*;
+
+use bevy::prelude::*;
+use bevy_egui::*;
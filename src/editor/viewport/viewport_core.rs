@@ -0,0 +1,318 @@
+use bevy::window::PrimaryWindow;
+
+use crate::core::core_core::{GameMode, GameState};
+use crate::editor::history::history_core::{EditorCommand, EditorHistory};
+use crate::editor::inspector::inspector_core::SelectedFrame;
+use crate::editor::viewport::*;
+
+/// Distance in screen pixels within which a resize handle can be grabbed.
+const HANDLE_GRAB_DISTANCE: f32 = 8.0;
+
+/// Boxes smaller than this (in sprite pixels) are discarded when a draw ends.
+const MIN_BOX_SIZE: f32 = 0.5;
+
+pub(crate) struct ViewportPlugin;
+
+impl Plugin for ViewportPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(ViewportState::default())
+            .add_systems(Update, viewport_mouse_system)
+            .add_systems(Update, delete_selected_box)
+            .add_systems(Update, gizmos_selected_box);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoxSelection {
+    pub kind: EditorBoxKind,
+    pub index: usize,
+}
+
+/// Which sides of a box a resize moves, `-1` for left/bottom and `1` for right/top.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct BoxHandle {
+    x: i8,
+    y: i8,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum BoxDrag {
+    Move { grab_offset: Vec2 },
+    Resize { handle: BoxHandle, min: Vec2, max: Vec2 },
+}
+
+#[derive(Resource)]
+pub struct ViewportState {
+    pub selected_box: Option<BoxSelection>,
+    pub draw_kind: EditorBoxKind,
+    drag: Option<BoxDrag>,
+    /// Set when the current drag started by drawing a new box.
+    drawing: bool,
+    /// Frame the selection belongs to, the selection is dropped when another frame is shown.
+    frame: Option<(String, usize)>,
+}
+
+impl Default for ViewportState {
+    fn default() -> Self {
+        ViewportState {
+            selected_box: None,
+            draw_kind: EditorBoxKind::Hurt,
+            drag: None,
+            drawing: false,
+            frame: None,
+        }
+    }
+}
+
+const HANDLES: [BoxHandle; 8] = [
+    BoxHandle { x: -1, y: -1 },
+    BoxHandle { x: 1, y: -1 },
+    BoxHandle { x: -1, y: 1 },
+    BoxHandle { x: 1, y: 1 },
+    BoxHandle { x: 0, y: -1 },
+    BoxHandle { x: 0, y: 1 },
+    BoxHandle { x: -1, y: 0 },
+    BoxHandle { x: 1, y: 0 },
+];
+
+fn handle_position(handle: BoxHandle, size: Vec2, offset: Vec2) -> Vec2 {
+    offset + Vec2::new(handle.x as f32, handle.y as f32) * size * 0.5
+}
+
+fn box_contains(size: Vec2, offset: Vec2, point: Vec2) -> bool {
+    let half = size.abs() * 0.5;
+    (point.x - offset.x).abs() <= half.x && (point.y - offset.y).abs() <= half.y
+}
+
+/// Rounds sprite-space coordinates to the half pixel, matching the precision of the authored data.
+fn snap(value: Vec2) -> Vec2 {
+    (value * 2.0).round() / 2.0
+}
+
+/// Converts a cursor position into sprite pixels relative to the sprite center.
+///
+/// The camera transform already carries the panel offset applied by `update_camera_transform`,
+/// what is left is undoing the sprite scale.
+fn screen_to_sprite(
+    camera: &Camera,
+    camera_transform: &Transform,
+    cursor: Vec2,
+) -> Option<Vec2> {
+    let world = camera.viewport_to_world_2d(&GlobalTransform::from(*camera_transform), cursor)?;
+    let sprite_transform = editor_sprite_transform();
+    Some((world - sprite_transform.translation.truncate()) / sprite_transform.scale.truncate())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn viewport_mouse_system(
+    mut egui_contexts: EguiContexts,
+    mut viewport: ResMut<ViewportState>,
+    selected_frame: Res<SelectedFrame>,
+    mut sprite_sheets: ResMut<EditorSpriteSheets>,
+    mut history: ResMut<EditorHistory>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    editor_camera: Res<EditorCamera>,
+    cameras: Query<(&Camera, &Transform)>,
+    game_state: Res<GameState>,
+) {
+    if game_state.mode != GameMode::Editor {
+        viewport.drag = None;
+        return;
+    }
+
+    let Some(camera_entity) = editor_camera.entity else { return; };
+    let Ok((camera, camera_transform)) = cameras.get(camera_entity) else { return; };
+    let Ok(window) = windows.get_single() else { return; };
+    // Releasing the button outside the window still has to end the drag, so a missing cursor only skips pointing
+    let point = window.cursor_position().and_then(|cursor| screen_to_sprite(camera, camera_transform, cursor));
+
+    let Some(frame_index) = selected_frame.frame_index else { return; };
+    let Some(sheet_id) = selected_frame.sprite_sheet_id.clone() else { return; };
+
+    let frame = Some((sheet_id.clone(), frame_index));
+    if viewport.frame != frame {
+        viewport.frame = frame;
+        viewport.selected_box = None;
+        viewport.drag = None;
+    }
+
+    let Some(frame_data) = sprite_sheets.selected_frame_data_mut(&selected_frame) else { return; };
+    let frame_before = frame_data.clone();
+    let label = edit_label(&viewport);
+
+    if mouse.just_pressed(MouseButton::Left) {
+        let Some(point) = point else { return; };
+        if egui_contexts.ctx_mut().is_pointer_over_area() {
+            return;
+        }
+
+        let grab_distance = HANDLE_GRAB_DISTANCE / EDITOR_SPRITE_SCALE;
+        viewport.drawing = false;
+
+        let selected_handle = viewport.selected_box.and_then(|selection| {
+            let editor_box = frame_data.boxes(selection.kind).get(selection.index)?;
+            let (size, offset) = (editor_box.size, editor_box.offset);
+            HANDLES.iter()
+                .find(|handle| handle_position(**handle, size, offset).distance(point) <= grab_distance)
+                .map(|handle| (*handle, size, offset))
+        });
+
+        if let Some((handle, size, offset)) = selected_handle {
+            viewport.drag = Some(BoxDrag::Resize {
+                handle,
+                min: offset - size.abs() * 0.5,
+                max: offset + size.abs() * 0.5,
+            });
+        } else if let Some((selection, offset)) = find_box_at(frame_data, point) {
+            viewport.selected_box = Some(selection);
+            viewport.drag = Some(BoxDrag::Move { grab_offset: offset - point });
+        } else {
+            let start = snap(point);
+            let kind = viewport.draw_kind;
+            let index = frame_data.push_box(kind, Vec2::ZERO, start);
+            viewport.selected_box = Some(BoxSelection { kind, index });
+            viewport.drag = Some(BoxDrag::Resize { handle: BoxHandle { x: 1, y: 1 }, min: start, max: start });
+            viewport.drawing = true;
+        }
+    }
+
+    let Some(selection) = viewport.selected_box else { return; };
+
+    if mouse.pressed(MouseButton::Left) {
+        let editor_box = frame_data.boxes_mut(selection.kind).get_mut(selection.index);
+        if let (Some(drag), Some(editor_box), Some(point)) = (viewport.drag, editor_box, point) {
+            update_drag(drag, &mut editor_box.size, &mut editor_box.offset, point);
+        }
+    }
+
+    if mouse.just_released(MouseButton::Left) && viewport.drag.take().is_some() && viewport.drawing {
+        viewport.drawing = false;
+        let too_small = frame_data.boxes(selection.kind)
+            .get(selection.index)
+            .is_some_and(|editor_box| editor_box.size.x < MIN_BOX_SIZE || editor_box.size.y < MIN_BOX_SIZE);
+        if too_small {
+            frame_data.remove_box(selection.kind, selection.index);
+            viewport.selected_box = None;
+        }
+    }
+
+    if let Some(label) = label.or_else(|| edit_label(&viewport)) {
+        history.record(label, EditorCommand::EditFrame {
+            sheet_id,
+            frame_index,
+            before: frame_before,
+            after: frame_data.clone(),
+        });
+    }
+}
+
+/// History label of the drag in progress, `None` when nothing is being dragged.
+fn edit_label(viewport: &ViewportState) -> Option<String> {
+    let selection = viewport.selected_box?;
+    let verb = match viewport.drag? {
+        _ if viewport.drawing => return Some(format!("Draw {}", selection.kind.label())),
+        BoxDrag::Move { .. } => "Move",
+        BoxDrag::Resize { .. } => "Resize",
+    };
+    Some(format!("{} {} {}", verb, selection.kind.label(), selection.index))
+}
+
+fn update_drag(drag: BoxDrag, size: &mut Vec2, offset: &mut Vec2, point: Vec2) {
+    match drag {
+        BoxDrag::Move { grab_offset } => {
+            *offset = snap(point + grab_offset);
+        }
+        BoxDrag::Resize { handle, mut min, mut max } => {
+            let point = snap(point);
+            match handle.x {
+                -1 => min.x = point.x,
+                1 => max.x = point.x,
+                _ => {}
+            }
+            match handle.y {
+                -1 => min.y = point.y,
+                1 => max.y = point.y,
+                _ => {}
+            }
+            *size = (max - min).abs();
+            *offset = (max + min) * 0.5;
+        }
+    }
+}
+
+fn delete_selected_box(
+    mut egui_contexts: EguiContexts,
+    mut viewport: ResMut<ViewportState>,
+    selected_frame: Res<SelectedFrame>,
+    mut sprite_sheets: ResMut<EditorSpriteSheets>,
+    mut history: ResMut<EditorHistory>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    game_state: Res<GameState>,
+) {
+    if game_state.mode != GameMode::Editor || !keyboard.just_pressed(KeyCode::Delete) {
+        return;
+    }
+    if egui_contexts.ctx_mut().wants_keyboard_input() {
+        return;
+    }
+
+    let Some(selection) = viewport.selected_box else { return; };
+    let Some(frame_index) = selected_frame.frame_index else { return; };
+    let Some(sheet_id) = selected_frame.sprite_sheet_id.clone() else { return; };
+    let Some(frame_data) = sprite_sheets.selected_frame_data_mut(&selected_frame) else { return; };
+
+    let frame_before = frame_data.clone();
+    frame_data.remove_box(selection.kind, selection.index);
+    history.record(format!("Remove {} {}", selection.kind.label(), selection.index), EditorCommand::EditFrame {
+        sheet_id,
+        frame_index,
+        before: frame_before,
+        after: frame_data.clone(),
+    });
+    viewport.selected_box = None;
+    viewport.drag = None;
+}
+
+/// Finds the smallest box under `point`, so nested boxes stay reachable.
+fn find_box_at(frame_data: &EditorFrameData, point: Vec2) -> Option<(BoxSelection, Vec2)> {
+    EditorBoxKind::ALL.into_iter()
+        .flat_map(|kind| frame_data.boxes(kind)
+            .iter()
+            .enumerate()
+            .map(move |(index, editor_box)| (BoxSelection { kind, index }, editor_box)))
+        .filter(|(_, editor_box)| box_contains(editor_box.size, editor_box.offset, point))
+        .min_by(|(_, a), (_, b)| (a.size.x * a.size.y).abs().total_cmp(&(b.size.x * b.size.y).abs()))
+        .map(|(selection, editor_box)| (selection, editor_box.offset))
+}
+
+fn gizmos_selected_box(
+    mut gizmos: Gizmos,
+    selected_frame: Res<SelectedFrame>,
+    sprite_sheets: Res<EditorSpriteSheets>,
+    viewport: Res<ViewportState>,
+    game_state: Res<GameState>,
+) {
+    if game_state.mode != GameMode::Editor {
+        return;
+    }
+
+    let Some(selection) = viewport.selected_box else { return; };
+    let Some(frame_data) = sprite_sheets.selected_frame_data(&selected_frame) else { return; };
+    let Some(editor_box) = frame_data.boxes(selection.kind).get(selection.index) else { return; };
+    let (size, offset) = (editor_box.size, editor_box.offset);
+
+    let transform = editor_sprite_transform();
+    let scale = transform.scale.truncate();
+    let origin = transform.translation.truncate();
+
+    gizmos.rect_2d(origin + offset * scale, 0.0, size * scale, Color::WHITE);
+    for handle in HANDLES {
+        gizmos.rect_2d(
+            origin + handle_position(handle, size, offset) * scale,
+            0.0,
+            Vec2::splat(HANDLE_GRAB_DISTANCE),
+            Color::WHITE,
+        );
+    }
+}
@@ -0,0 +1,632 @@
+use bevy::utils::HashMap;
+
+use crate::core::core_core::{GameMode, GameState};
+use crate::game::character::*;
+use crate::game::character::character_gui::CharacterGuiPlugin;
+use crate::game::collision::collision_core::{CollisionBody, Facing, HitEvent};
+use crate::game::game_core::{Controller, Player};
+use crate::game::moves::moves_core::{MoveDefinition, MoveList};
+use crate::game::round::round_core::RoundState;
+use crate::game::input::input_core::{sample_player_inputs, InputButton, InputFrame, InputHistory, PlayerInputs};
+use crate::game::simulation::simulation_core::{to_subpixels, Position, SimulationRng, SimulationSchedule, SimulationSet, SUBPIXELS_PER_UNIT};
+
+pub(crate) struct CharacterPlugin;
+
+impl Plugin for CharacterPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(CharacterGuiPlugin)
+            .insert_resource(CharacterAnimations::default())
+            .add_systems(SimulationSchedule, read_character_input.after(sample_player_inputs).in_set(SimulationSet::Input))
+            .insert_resource(FrameAdvantage::default())
+            .add_event::<DamageEvent>()
+            .add_systems(SimulationSchedule, (track_frame_advantage, apply_hit_reactions).chain().in_set(SimulationSet::Reaction));
+    }
+}
+
+/// Speeds and accelerations are in subpixels per tick.
+const WALK_FORWARD_SPEED: i32 = 400;
+const WALK_BACK_SPEED: i32 = 300;
+const JUMP_VELOCITY: i32 = 1800;
+const GRAVITY: i32 = 100;
+const WALL_SLIDE_SPEED: i32 = 200;
+/// Ticks spent on the ground before waking up.
+const KNOCKDOWN_TICKS: u32 = 40;
+/// Hits a juggled character takes before further hits whiff.
+const JUGGLE_LIMIT: u32 = 4;
+/// Percentage of the landing speed kept by a ground bounce, and of the speed toward the wall by a wall bounce.
+const BOUNCE_PERCENT: i32 = 60;
+/// One in this many ticks a random controller changes its input.
+const RANDOM_INPUT_CHANGE_CHANCE: u32 = 8;
+/// Slows down pushback on the ground while stunned.
+const STUN_FRICTION: i32 = 100;
+
+/// Height characters stand at, in subpixels.
+pub const GROUND_Y: i32 = 0;
+/// Characters stay within `-STAGE_HALF_WIDTH..=STAGE_HALF_WIDTH` subpixels, the bounds act as walls.
+pub const STAGE_HALF_WIDTH: i32 = 600 * SUBPIXELS_PER_UNIT;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CharacterAttack {
+    Jab,
+    Cross,
+}
+
+impl CharacterAttack {
+    pub const ALL: [CharacterAttack; 2] = [CharacterAttack::Jab, CharacterAttack::Cross];
+
+    /// Button performing the attack.
+    pub fn button(&self) -> InputButton {
+        match self {
+            CharacterAttack::Jab => InputButton::LightPunch,
+            CharacterAttack::Cross => InputButton::MediumPunch,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CharacterState {
+    #[default]
+    Idle,
+    WalkForward,
+    WalkBack,
+    Crouch,
+    Jump,
+    Land,
+    Attack(CharacterAttack),
+    /// Special move, an index in the `MoveList`.
+    Special(usize),
+    Hitstun,
+    Blockstun,
+    /// Launched or hit in the air, falling until the ground knocks the character down.
+    Juggle,
+    /// Juggled into the wall by a wall bounce hit, flying back toward the opponent.
+    WallBounce,
+    /// Juggled into the wall without a bounce, sliding down it.
+    WallSplat,
+    Knockdown,
+    WallLand,
+    WallSlide,
+}
+
+impl CharacterState {
+    pub const ALL: [CharacterState; 16] = [
+        CharacterState::Idle,
+        CharacterState::WalkForward,
+        CharacterState::WalkBack,
+        CharacterState::Crouch,
+        CharacterState::Jump,
+        CharacterState::Land,
+        CharacterState::Attack(CharacterAttack::Jab),
+        CharacterState::Attack(CharacterAttack::Cross),
+        CharacterState::Hitstun,
+        CharacterState::Blockstun,
+        CharacterState::Juggle,
+        CharacterState::WallBounce,
+        CharacterState::WallSplat,
+        CharacterState::Knockdown,
+        CharacterState::WallLand,
+        CharacterState::WallSlide,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            CharacterState::Idle => "Idle",
+            CharacterState::WalkForward => "Walk Forward",
+            CharacterState::WalkBack => "Walk Back",
+            CharacterState::Crouch => "Crouch",
+            CharacterState::Jump => "Jump",
+            CharacterState::Land => "Land",
+            CharacterState::Attack(CharacterAttack::Jab) => "Jab",
+            CharacterState::Attack(CharacterAttack::Cross) => "Cross",
+            CharacterState::Special(_) => "Special",
+            CharacterState::Hitstun => "Hitstun",
+            CharacterState::Blockstun => "Blockstun",
+            CharacterState::Juggle => "Juggle",
+            CharacterState::WallBounce => "Wall Bounce",
+            CharacterState::WallSplat => "Wall Splat",
+            CharacterState::Knockdown => "Knockdown",
+            CharacterState::WallLand => "Wall Land",
+            CharacterState::WallSlide => "Wall Slide",
+        }
+    }
+
+    /// States in which the character stands and accepts new actions.
+    pub fn is_actionable(&self) -> bool {
+        matches!(self, CharacterState::Idle | CharacterState::WalkForward | CharacterState::WalkBack | CharacterState::Crouch)
+    }
+
+    /// Airborne hit states, ended by landing rather than by a stun duration.
+    pub fn is_juggled(&self) -> bool {
+        matches!(self, CharacterState::Juggle | CharacterState::WallBounce | CharacterState::WallSplat)
+    }
+}
+
+/// Inputs of the current and previous tick, presses are told apart from holds by comparing both.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Hash, Component)]
+pub struct CharacterInput {
+    pub current: InputFrame,
+    pub previous: InputFrame,
+    /// Index in the `MoveList` of the special move input on this tick.
+    pub special: Option<usize>,
+}
+
+impl CharacterInput {
+    /// Moves the current frame to `previous` and makes `frame` current.
+    pub fn push(&mut self, frame: InputFrame) {
+        self.previous = self.current;
+        self.current = frame;
+    }
+
+    pub fn just_pressed(&self, button: InputButton) -> bool {
+        self.current.pressed(button) && !self.previous.pressed(button)
+    }
+
+    /// Attack pressed on this tick, the first one of `CharacterAttack::ALL` wins when several are.
+    pub fn attack(&self) -> Option<CharacterAttack> {
+        CharacterAttack::ALL.into_iter().find(|attack| self.just_pressed(attack.button()))
+    }
+}
+
+/// Stance a character blocks in, high and mid hits are blocked by both.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Guard {
+    Standing,
+    Crouching,
+}
+
+impl Guard {
+    pub fn blocks(&self, hit_level: EditorHitLevel) -> bool {
+        match hit_level {
+            EditorHitLevel::High | EditorHitLevel::Mid => true,
+            EditorHitLevel::Low => *self == Guard::Crouching,
+            EditorHitLevel::Overhead => *self == Guard::Standing,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Hash, Component)]
+pub struct CharacterStateMachine {
+    pub state: CharacterState,
+    /// Ticks spent in `state`, 0 on the tick it was entered.
+    pub ticks: u32,
+    /// Ticks left in hitstun, blockstun or knockdown.
+    pub stun: u32,
+    /// Hits taken since the character was launched, reset on knockdown.
+    pub juggle_hits: u32,
+    /// Bounces granted by the hits of the current juggle, each used at most once.
+    pub ground_bounce: bool,
+    pub wall_bounce: bool,
+    /// Ticks left frozen after a hit connected, neither the state nor the animation advance meanwhile.
+    pub hitstop: u32,
+    /// Set whenever `state` is entered, until the animation restarts.
+    pub entered: bool,
+    /// Subpixels per tick.
+    pub velocity: IVec2,
+}
+
+impl CharacterStateMachine {
+    pub fn set_state(&mut self, state: CharacterState) {
+        self.state = state;
+        self.ticks = 0;
+        self.entered = true;
+    }
+
+    /// Whether `state` was entered since the last call, the animation restarts then.
+    pub fn take_entered(&mut self) -> bool {
+        std::mem::take(&mut self.entered)
+    }
+
+    pub fn is_frozen(&self) -> bool {
+        self.hitstop > 0
+    }
+
+    /// Enters a stun state for `ticks`, sliding at `pushback` subpixels per tick until friction stops it.
+    pub fn enter_stun(&mut self, state: CharacterState, ticks: u32, pushback: i32) {
+        self.set_state(state);
+        self.stun = ticks;
+        self.velocity = IVec2::new(pushback, 0);
+    }
+
+    /// Sends the character flying at `velocity`, counting the hit toward the juggle limit.
+    pub fn launch(&mut self, velocity: IVec2, properties: &EditorHitProperties) {
+        self.set_state(CharacterState::Juggle);
+        self.velocity = velocity;
+        self.juggle_hits += 1;
+        self.ground_bounce |= properties.ground_bounce;
+        self.wall_bounce |= properties.wall_bounce;
+    }
+
+    /// Hits whiff on knocked down characters and past the juggle limit.
+    pub fn is_invulnerable(&self) -> bool {
+        self.state == CharacterState::Knockdown || (self.state.is_juggled() && self.juggle_hits >= JUGGLE_LIMIT)
+    }
+
+    fn knock_down(&mut self) {
+        self.set_state(CharacterState::Knockdown);
+        self.stun = KNOCKDOWN_TICKS;
+        self.velocity = IVec2::ZERO;
+        self.juggle_hits = 0;
+        self.ground_bounce = false;
+        self.wall_bounce = false;
+    }
+
+    /// Guard held by holding back, on the ground while free to act or already blocking.
+    pub fn guard(&self, input: &CharacterInput, facing: Facing, airborne: bool) -> Option<Guard> {
+        let can_block = self.state.is_actionable() || self.state == CharacterState::Blockstun;
+        let holding_back = input.current.horizontal() as i32 * facing.direction() < 0;
+        if airborne || !can_block || !holding_back {
+            return None;
+        }
+
+        Some(if input.current.vertical() < 0 { Guard::Crouching } else { Guard::Standing })
+    }
+
+    /// Advances the state and position by one tick, or only counts down hitstop while frozen.
+    /// `animation_finished` tells whether the clip of the current state played to its end.
+    pub fn step(&mut self, input: &CharacterInput, facing: Facing, position: &mut IVec2, animation_finished: bool) {
+        if self.is_frozen() {
+            self.hitstop -= 1;
+            return;
+        }
+
+        self.ticks += 1;
+        let airborne = position.y > GROUND_Y;
+
+        match self.state {
+            state if state.is_actionable() => self.step_actionable(input, facing),
+            CharacterState::Land | CharacterState::Attack(_) | CharacterState::Special(_) if animation_finished => self.set_state(CharacterState::Idle),
+            CharacterState::WallLand if animation_finished => self.set_state(CharacterState::WallSlide),
+            CharacterState::WallSlide if input.just_pressed(InputButton::Up) => {
+                let away = -position.x.signum();
+                self.velocity = IVec2::new(away * WALK_FORWARD_SPEED, JUMP_VELOCITY);
+                self.set_state(CharacterState::Jump);
+            }
+            CharacterState::Hitstun | CharacterState::Blockstun | CharacterState::Knockdown => {
+                if !airborne {
+                    let friction = self.velocity.x.abs().min(STUN_FRICTION);
+                    self.velocity.x -= self.velocity.x.signum() * friction;
+                }
+                self.stun = self.stun.saturating_sub(1);
+                if self.stun == 0 && !airborne {
+                    self.set_state(CharacterState::Idle);
+                }
+            }
+            _ => {}
+        }
+
+        if matches!(self.state, CharacterState::WallSlide | CharacterState::WallSplat) {
+            self.velocity = IVec2::new(0, -WALL_SLIDE_SPEED);
+        } else if airborne || self.velocity.y > 0 {
+            self.velocity.y -= GRAVITY;
+        }
+
+        *position += self.velocity;
+
+        let at_wall = position.x.abs() >= STAGE_HALF_WIDTH;
+        position.x = position.x.clamp(-STAGE_HALF_WIDTH, STAGE_HALF_WIDTH);
+
+        if position.y <= GROUND_Y {
+            position.y = GROUND_Y;
+            let landing_speed = -self.velocity.y;
+            self.velocity.y = 0;
+            if matches!(self.state, CharacterState::Jump | CharacterState::WallLand | CharacterState::WallSlide) {
+                self.velocity.x = 0;
+                self.set_state(CharacterState::Land);
+            } else if self.state.is_juggled() && self.ground_bounce {
+                self.ground_bounce = false;
+                self.velocity.y = landing_speed * BOUNCE_PERCENT / 100;
+                self.set_state(CharacterState::Juggle);
+            } else if self.state.is_juggled() {
+                self.knock_down();
+            }
+        } else if at_wall && self.state == CharacterState::Jump && self.velocity.y <= 0 {
+            self.velocity = IVec2::ZERO;
+            self.set_state(CharacterState::WallLand);
+        } else if at_wall && matches!(self.state, CharacterState::Juggle | CharacterState::WallBounce) && self.velocity.x.signum() == position.x.signum() {
+            if self.wall_bounce {
+                self.wall_bounce = false;
+                self.velocity.x = -self.velocity.x * BOUNCE_PERCENT / 100;
+                self.set_state(CharacterState::WallBounce);
+            } else {
+                self.set_state(CharacterState::WallSplat);
+            }
+        }
+    }
+
+    fn step_actionable(&mut self, input: &CharacterInput, facing: Facing) {
+        let horizontal = input.current.horizontal() as i32;
+        let vertical = input.current.vertical();
+        let forward = horizontal * facing.direction();
+
+        let state = if let Some(special) = input.special {
+            CharacterState::Special(special)
+        } else if let Some(attack) = input.attack() {
+            CharacterState::Attack(attack)
+        } else if vertical > 0 {
+            CharacterState::Jump
+        } else if vertical < 0 {
+            CharacterState::Crouch
+        } else if forward > 0 {
+            CharacterState::WalkForward
+        } else if forward < 0 {
+            CharacterState::WalkBack
+        } else {
+            CharacterState::Idle
+        };
+
+        self.velocity = match state {
+            CharacterState::WalkForward => IVec2::new(horizontal * WALK_FORWARD_SPEED, 0),
+            CharacterState::WalkBack => IVec2::new(horizontal * WALK_BACK_SPEED, 0),
+            CharacterState::Jump => IVec2::new(horizontal * WALK_FORWARD_SPEED, JUMP_VELOCITY),
+            _ => IVec2::ZERO,
+        };
+
+        if state != self.state {
+            self.set_state(state);
+        }
+    }
+}
+
+/// Ticks the attacker recovered before the defender after an interaction, negative when the defender was first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameAdvantageResult {
+    pub attacker: Entity,
+    pub ticks: i32,
+    pub blocked: bool,
+}
+
+#[derive(Debug, Clone)]
+struct PendingFrameAdvantage {
+    attacker: Entity,
+    defender: Entity,
+    blocked: bool,
+    /// Ticks since the hit connected.
+    ticks: u32,
+    attacker_recovered: Option<u32>,
+    defender_recovered: Option<u32>,
+}
+
+/// Frame advantage of the last hit or block, measured once both characters can act again.
+#[derive(Debug, Default, Clone, Resource)]
+pub struct FrameAdvantage {
+    pub last: Option<FrameAdvantageResult>,
+    /// Results measured so far, tells a new result apart from an identical one.
+    pub measured: u64,
+    pending: Option<PendingFrameAdvantage>,
+}
+
+impl FrameAdvantage {
+    /// Starts measuring from a new hit, replacing the interaction still being measured.
+    fn start(&mut self, attacker: Entity, defender: Entity, blocked: bool) {
+        self.pending = Some(PendingFrameAdvantage {
+            attacker,
+            defender,
+            blocked,
+            ticks: 0,
+            attacker_recovered: None,
+            defender_recovered: None,
+        });
+    }
+}
+
+/// Sheet and clip played while in a state.
+#[derive(Debug, Clone)]
+pub struct StateAnimation {
+    pub sheet_id: String,
+    /// Named clip of the sheet, the whole sheet when `None` or missing.
+    pub clip: Option<String>,
+    /// Overrides the loop mode of the clip, states that end with their animation need `Once`.
+    pub loop_mode: EditorClipLoopMode,
+}
+
+impl StateAnimation {
+    fn new(sheet_id: &str, loop_mode: EditorClipLoopMode) -> Self {
+        StateAnimation { sheet_id: sheet_id.to_string(), clip: None, loop_mode }
+    }
+
+    /// Resolves the clip to play from the sheet.
+    pub fn clip(&self, sheet_info: &EditorSpriteSheetInfo) -> EditorAnimationClip {
+        let clip = self.clip.as_ref()
+            .and_then(|name| sheet_info.clips.iter().find(|clip| &clip.name == name))
+            .cloned()
+            .unwrap_or_else(|| sheet_info.whole_sheet_clip(self.loop_mode));
+        EditorAnimationClip { loop_mode: self.loop_mode, ..clip }
+    }
+}
+
+/// Animation of every character state, shared by all characters.
+#[derive(Resource)]
+pub struct CharacterAnimations {
+    pub states: HashMap<CharacterState, StateAnimation>,
+}
+
+impl CharacterAnimations {
+    /// Animation of `state`, special moves take theirs from the move list.
+    pub fn animation(&self, state: CharacterState, move_list: &MoveList) -> Option<StateAnimation> {
+        match state {
+            CharacterState::Special(index) => move_list.moves.get(index).map(MoveDefinition::animation),
+            _ => self.states.get(&state).cloned(),
+        }
+    }
+}
+
+impl Default for CharacterAnimations {
+    fn default() -> Self {
+        use EditorClipLoopMode::*;
+
+        let states = CharacterState::ALL.into_iter()
+            .filter_map(|state| Some((state, match state {
+                CharacterState::Idle => StateAnimation::new("player-idle", Loop),
+                CharacterState::WalkForward | CharacterState::WalkBack => StateAnimation::new("player-run", Loop),
+                CharacterState::Crouch => StateAnimation::new("player-land", HoldLast),
+                CharacterState::Jump => StateAnimation::new("player-jump", HoldLast),
+                CharacterState::Land => StateAnimation::new("player-land", Once),
+                CharacterState::Attack(CharacterAttack::Jab) => StateAnimation::new("player-punch-jab", Once),
+                CharacterState::Attack(CharacterAttack::Cross) => StateAnimation::new("player-punch-cross", Once),
+                CharacterState::Hitstun => StateAnimation::new("player-wall-land", HoldLast),
+                CharacterState::Blockstun => StateAnimation::new("player-wall-slide", Loop),
+                CharacterState::Juggle => StateAnimation::new("player-jump", HoldLast),
+                CharacterState::WallBounce => StateAnimation::new("player-wall-land", Once),
+                CharacterState::WallSplat => StateAnimation::new("player-wall-slide", Loop),
+                CharacterState::Knockdown => StateAnimation::new("player-land", HoldLast),
+                CharacterState::WallLand => StateAnimation::new("player-wall-land", Once),
+                CharacterState::WallSlide => StateAnimation::new("player-wall-slide", Loop),
+                CharacterState::Special(_) => return None,
+            })))
+            .collect();
+
+        CharacterAnimations { states }
+    }
+}
+
+/// Feeds each character the input of its controller once per tick, so presses are seen on exactly one tick.
+pub(crate) fn read_character_input(
+    game_state: Res<GameState>,
+    player_inputs: Res<PlayerInputs>,
+    round_state: Res<RoundState>,
+    mut rng: ResMut<SimulationRng>,
+    mut query: Query<(&Player, &Controller, &Facing, &mut CharacterInput, &mut InputHistory)>,
+) {
+    if !game_state.mode.is_simulated() {
+        return;
+    }
+
+    // Query order depends on archetypes, random inputs are drawn in player order instead
+    let mut characters: Vec<_> = query.iter_mut().collect();
+    characters.sort_by_key(|(player, ..)| player.index);
+
+    for (player, controller, facing, mut input, mut history) in characters {
+        // Both sides are driven by their peer in netplay, and the dummy drives player two's inputs in training
+        let controller = if matches!(game_state.mode, GameMode::Netplay | GameMode::Training) { Controller::Human } else { *controller };
+        let frame = match controller {
+            _ if !round_state.accepts_input() => InputFrame::default(),
+            Controller::Human => player_inputs.0.get(player.index).copied().unwrap_or_default(),
+            Controller::Idle => InputFrame::default(),
+            Controller::Random => random_input(&mut rng, input.current),
+        };
+        input.push(frame);
+        history.push(frame, facing.direction());
+    }
+}
+
+/// Mostly keeps holding the previous direction, now and then switching to a random one and pressing a random button.
+fn random_input(rng: &mut SimulationRng, previous: InputFrame) -> InputFrame {
+    let mut frame = InputFrame::default();
+    for button in InputButton::ALL.into_iter().filter(InputButton::is_direction) {
+        frame.set(button, previous.pressed(button));
+    }
+
+    if rng.below(RANDOM_INPUT_CHANGE_CHANCE) == 0 {
+        let horizontal = rng.below(3) as i32 - 1;
+        let vertical = rng.below(3) as i32 - 1;
+        frame.set(InputButton::Left, horizontal < 0);
+        frame.set(InputButton::Right, horizontal > 0);
+        frame.set(InputButton::Down, vertical < 0);
+        frame.set(InputButton::Up, vertical > 0);
+
+        let buttons: Vec<InputButton> = InputButton::ALL.into_iter().filter(|button| !button.is_direction()).collect();
+        let pick = rng.below(buttons.len() as u32 * 2) as usize;
+        if let Some(button) = buttons.get(pick) {
+            frame.set(*button, true);
+        }
+    }
+
+    frame
+}
+
+/// Health taken from `entity` by a hit, the chip damage when it was blocked.
+#[derive(Debug, Clone, Event)]
+pub struct DamageEvent {
+    pub entity: Entity,
+    pub damage: u32,
+}
+
+/// Freezes both characters for the hitstop of the hit, then stuns or launches the defender along the knockback.
+/// Blocked hits deal chip damage and only push the defender back.
+pub(crate) fn apply_hit_reactions(
+    mut hit_events: EventReader<HitEvent>,
+    mut damage_events: EventWriter<DamageEvent>,
+    mut frame_advantage: ResMut<FrameAdvantage>,
+    mut query: Query<(&mut CharacterStateMachine, &CharacterInput, &Facing, &Position, &CollisionBody)>,
+) {
+    for event in hit_events.read() {
+        let Ok([(mut attacker, .., attacker_position, _), (mut defender, input, facing, position, body)]) =
+            query.get_many_mut([event.attacker, event.defender]) else { continue; };
+        let properties = &event.properties;
+        let airborne = position.0.y > GROUND_Y;
+
+        if defender.is_invulnerable() {
+            continue;
+        }
+
+        let away = if position.0.x == attacker_position.0.x { -facing.direction() } else { (position.0.x - attacker_position.0.x).signum() };
+        let knockback = IVec2::new(away * to_subpixels(properties.knockback.x * body.scale), to_subpixels(properties.knockback.y * body.scale));
+
+        let blocked = defender.guard(input, *facing, airborne).is_some_and(|guard| guard.blocks(properties.hit_level));
+        if blocked {
+            defender.enter_stun(CharacterState::Blockstun, properties.blockstun, knockback.x);
+        } else if airborne || knockback.y > 0 {
+            defender.launch(knockback, properties);
+        } else {
+            defender.enter_stun(CharacterState::Hitstun, properties.hitstun, knockback.x);
+        }
+
+        damage_events.send(DamageEvent {
+            entity: event.defender,
+            damage: if blocked { properties.chip_damage } else { properties.damage },
+        });
+
+        attacker.hitstop = properties.hitstop;
+        defender.hitstop = properties.hitstop;
+        frame_advantage.start(event.attacker, event.defender, blocked);
+    }
+}
+
+/// Records the tick each side of the pending interaction becomes actionable again.
+fn track_frame_advantage(
+    mut frame_advantage: ResMut<FrameAdvantage>,
+    query: Query<&CharacterStateMachine>,
+) {
+    let Some(pending) = frame_advantage.pending.as_mut() else { return; };
+    let (Ok(attacker), Ok(defender)) = (query.get(pending.attacker), query.get(pending.defender)) else {
+        frame_advantage.pending = None;
+        return;
+    };
+
+    pending.ticks += 1;
+    let recovered = |state_machine: &CharacterStateMachine| state_machine.state.is_actionable() && !state_machine.is_frozen();
+    if pending.attacker_recovered.is_none() && recovered(attacker) {
+        pending.attacker_recovered = Some(pending.ticks);
+    }
+    if pending.defender_recovered.is_none() && recovered(defender) {
+        pending.defender_recovered = Some(pending.ticks);
+    }
+
+    if let (Some(attacker_recovered), Some(defender_recovered)) = (pending.attacker_recovered, pending.defender_recovered) {
+        let (attacker, blocked) = (pending.attacker, pending.blocked);
+        frame_advantage.last = Some(FrameAdvantageResult { attacker, ticks: defender_recovered as i32 - attacker_recovered as i32, blocked });
+        frame_advantage.measured += 1;
+        frame_advantage.pending = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::editor::editor_core::{EditorBoxKind, EditorSpriteSheetsData, SPRITE_SHEETS_PATH};
+
+    #[test]
+    fn shipped_state_animations_have_push_boxes_on_every_frame() {
+        let data: EditorSpriteSheetsData = serde_json::from_str(&std::fs::read_to_string(SPRITE_SHEETS_PATH).unwrap()).unwrap();
+        for (state, animation) in &CharacterAnimations::default().states {
+            let sheet_info = data.sheets.iter()
+                .find(|sheet_info| sheet_info.id == animation.sheet_id)
+                .unwrap_or_else(|| panic!("no sheet {} for {:?}", animation.sheet_id, state));
+
+            for (frame_index, frame) in sheet_info.frames.iter().enumerate() {
+                assert!(frame.boxes(EditorBoxKind::Push).iter().any(|push_box| !push_box.is_empty()),
+                    "{} frame {} has no push box", sheet_info.id, frame_index);
+            }
+        }
+    }
+}
@@ -0,0 +1,40 @@
+use bevy_egui::{egui, EguiContexts};
+
+use crate::core::core_core::GameState;
+use crate::core::core_gui::CoreGuiState;
+use crate::game::character::*;
+use crate::game::character::character_core::FrameAdvantage;
+
+pub(crate) struct CharacterGuiPlugin;
+
+impl Plugin for CharacterGuiPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, display_frame_advantage);
+    }
+}
+
+fn display_frame_advantage(
+    mut egui_contexts: EguiContexts,
+    game_state: Res<GameState>,
+    gui_state: Res<CoreGuiState>,
+    frame_advantage: Res<FrameAdvantage>,
+) {
+    if !game_state.mode.is_simulated() || !gui_state.show_frame_advantage {
+        return;
+    }
+
+    let text = match frame_advantage.last {
+        Some(result) => format!("{:+} on {}", result.ticks, if result.blocked { "block" } else { "hit" }),
+        None => "-".to_string(),
+    };
+
+    egui::Area::new(egui::Id::new("frame_advantage"))
+        .anchor(egui::Align2::CENTER_BOTTOM, egui::vec2(0.0, -8.0))
+        .interactable(false)
+        .show(egui_contexts.ctx_mut(), |ui| {
+            egui::Frame::popup(ui.style()).show(ui, |ui| {
+                ui.strong("Frame Advantage");
+                ui.monospace(text);
+            });
+        });
+}
@@ -0,0 +1,6 @@
+pub mod character_core;
+pub mod character_gui;
+
+use crate::editor::editor_core::*;
+
+use bevy::prelude::*;
@@ -0,0 +1,422 @@
+use bevy::math::Rect;
+
+use crate::core::core_core::GameState;
+use crate::game::collision::*;
+use crate::game::game_core::Player;
+use crate::game::simulation::simulation_core::{to_subpixels, Position, SimulationSchedule, SimulationSet};
+
+pub(crate) struct CollisionPlugin;
+
+impl Plugin for CollisionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<HitEvent>()
+            .add_event::<ThrowEvent>()
+            .add_event::<PushEvent>()
+            .add_systems(SimulationSchedule, detect_collisions.in_set(SimulationSet::Collision))
+            .add_systems(SimulationSchedule, (resolve_push_contacts, log_contacts).in_set(SimulationSet::Reaction));
+    }
+}
+
+/// Direction a character looks at, boxes are mirrored horizontally when facing left.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Component)]
+pub enum Facing {
+    #[default]
+    Right,
+    Left,
+}
+
+impl Facing {
+    /// Facing from `x` toward `target_x`.
+    pub fn toward<T: PartialOrd>(x: T, target_x: T) -> Facing {
+        if target_x < x { Facing::Left } else { Facing::Right }
+    }
+
+    pub fn direction(&self) -> i32 {
+        match self {
+            Facing::Right => 1,
+            Facing::Left => -1,
+        }
+    }
+
+    pub fn sign(&self) -> f32 {
+        match self {
+            Facing::Right => 1.0,
+            Facing::Left => -1.0,
+        }
+    }
+}
+
+/// Entity whose boxes are taken from a frame of a sprite sheet, kept in sync with its animation.
+#[derive(Debug, Clone, Component)]
+pub struct CollisionBody {
+    pub sheet_id: String,
+    pub frame: usize,
+    /// World units per sprite pixel.
+    pub scale: f32,
+}
+
+/// Hit groups that already connected with each defender during the current attack.
+/// The attack ends, and the tracker is cleared, on the first frame without hit or throw boxes.
+#[derive(Debug, Default, Clone, Component)]
+pub struct HitTracker {
+    connected: Vec<(Entity, u32)>,
+}
+
+impl HitTracker {
+    /// Marks the group as connected with `defender`, returns false if it already was.
+    pub fn try_connect(&mut self, defender: Entity, hit_group: u32) -> bool {
+        if self.connected.contains(&(defender, hit_group)) {
+            return false;
+        }
+
+        self.connected.push((defender, hit_group));
+        true
+    }
+
+    /// Defenders and the hit groups that connected with them, in connection order.
+    pub fn connected(&self) -> &[(Entity, u32)] {
+        &self.connected
+    }
+
+    pub fn clear(&mut self) {
+        self.connected.clear();
+    }
+}
+
+/// A hitbox of `attacker` overlapped a hurtbox of `defender`.
+#[derive(Debug, Clone, Event)]
+pub struct HitEvent {
+    pub attacker: Entity,
+    pub defender: Entity,
+    pub properties: EditorHitProperties,
+    /// Center of the overlap between the two boxes, in world space.
+    pub point: Vec2,
+}
+
+/// A throw box of `attacker` overlapped a throwable box of `defender`.
+#[derive(Debug, Clone, Event)]
+pub struct ThrowEvent {
+    pub attacker: Entity,
+    pub defender: Entity,
+    pub properties: EditorHitProperties,
+}
+
+/// Push boxes of two entities overlap by `overlap` world units horizontally.
+#[derive(Debug, Clone, Event)]
+pub struct PushEvent {
+    pub entity: Entity,
+    pub other: Entity,
+    pub overlap: f32,
+}
+
+/// A box of the current frame placed in world space.
+#[derive(Debug, Clone)]
+pub struct WorldBox {
+    pub rect: Rect,
+    pub properties: EditorHitProperties,
+}
+
+/// Places the non-empty boxes of `kind` around `position`, mirroring them when facing left.
+pub fn world_boxes(frame_data: &EditorFrameData, kind: EditorBoxKind, position: Vec2, scale: f32, facing: Facing) -> Vec<WorldBox> {
+    frame_data.active_boxes(kind)
+        .map(|editor_box| {
+            let offset = editor_box.offset * scale * Vec2::new(facing.sign(), 1.0);
+            WorldBox {
+                rect: Rect::from_center_size(position + offset, editor_box.size * scale),
+                properties: editor_box.hit_properties(),
+            }
+        })
+        .collect()
+}
+
+/// Boxes touching only along an edge do not overlap.
+pub fn overlap(a: &Rect, b: &Rect) -> Option<Rect> {
+    let intersection = a.intersect(*b);
+    (!intersection.is_empty()).then_some(intersection)
+}
+
+/// First attacker box of each hit group that overlaps any defender box, in box order.
+fn first_contacts(attack_boxes: &[WorldBox], target_boxes: &[WorldBox]) -> Vec<(EditorHitProperties, Rect)> {
+    let mut contacts: Vec<(EditorHitProperties, Rect)> = Vec::new();
+
+    for attack_box in attack_boxes {
+        if contacts.iter().any(|(properties, _)| properties.hit_group == attack_box.properties.hit_group) {
+            continue;
+        }
+
+        if let Some(rect) = target_boxes.iter().find_map(|target_box| overlap(&attack_box.rect, &target_box.rect)) {
+            contacts.push((attack_box.properties.clone(), rect));
+        }
+    }
+
+    contacts
+}
+
+struct BodyBoxes {
+    entity: Entity,
+    player: Option<usize>,
+    hit: Vec<WorldBox>,
+    hurt: Vec<WorldBox>,
+    push: Vec<WorldBox>,
+    throw: Vec<WorldBox>,
+    throwable: Vec<WorldBox>,
+}
+
+#[allow(clippy::type_complexity)]
+pub(crate) fn detect_collisions(
+    game_state: Res<GameState>,
+    sprite_sheets: Res<EditorSpriteSheets>,
+    mut query: Query<(Entity, &Position, &CollisionBody, Option<&Facing>, Option<&Player>, Option<&mut HitTracker>)>,
+    mut hit_events: EventWriter<HitEvent>,
+    mut throw_events: EventWriter<ThrowEvent>,
+    mut push_events: EventWriter<PushEvent>,
+) {
+    if !game_state.mode.is_simulated() {
+        return;
+    }
+
+    let mut bodies: Vec<BodyBoxes> = Vec::new();
+    for (entity, position, body, facing, player, tracker) in &mut query {
+        let Some(sheet_info) = sprite_sheets.sheet_info(&body.sheet_id) else { continue; };
+        let Some(frame_data) = sheet_info.frames.get(body.frame) else { continue; };
+        let facing = facing.copied().unwrap_or_default();
+        let boxes = |kind| world_boxes(frame_data, kind, position.to_world(), body.scale, facing);
+
+        let body_boxes = BodyBoxes {
+            entity,
+            player: player.map(|player| player.index),
+            hit: boxes(EditorBoxKind::Hit),
+            hurt: boxes(EditorBoxKind::Hurt),
+            push: boxes(EditorBoxKind::Push),
+            throw: boxes(EditorBoxKind::Throw),
+            throwable: boxes(EditorBoxKind::Throwable),
+        };
+
+        if let Some(mut tracker) = tracker {
+            if body_boxes.hit.is_empty() && body_boxes.throw.is_empty() {
+                tracker.clear();
+            }
+        }
+
+        bodies.push(body_boxes);
+    }
+
+    // Query order depends on archetypes and entity ids on spawn order, contacts are resolved by player instead,
+    // bodies without a player come last
+    bodies.sort_by_key(|body_boxes| (body_boxes.player.is_none(), body_boxes.player, body_boxes.entity));
+
+    for (index, attacker) in bodies.iter().enumerate() {
+        for (other_index, defender) in bodies.iter().enumerate() {
+            if index == other_index {
+                continue;
+            }
+
+            let mut connects = |hit_group| query.get_mut(attacker.entity)
+                .ok()
+                .and_then(|(.., tracker)| tracker)
+                .is_none_or(|mut tracker| tracker.try_connect(defender.entity, hit_group));
+
+            for (properties, rect) in first_contacts(&attacker.hit, &defender.hurt) {
+                if connects(properties.hit_group) {
+                    hit_events.send(HitEvent {
+                        attacker: attacker.entity,
+                        defender: defender.entity,
+                        properties,
+                        point: rect.center(),
+                    });
+                }
+            }
+
+            for (properties, _) in first_contacts(&attacker.throw, &defender.throwable) {
+                if connects(properties.hit_group) {
+                    throw_events.send(ThrowEvent {
+                        attacker: attacker.entity,
+                        defender: defender.entity,
+                        properties,
+                    });
+                }
+            }
+
+            if index < other_index {
+                let push_overlap = attacker.push.iter()
+                    .flat_map(|push_box| defender.push.iter().filter_map(|other_box| overlap(&push_box.rect, &other_box.rect)))
+                    .map(|rect| rect.width())
+                    .reduce(f32::max);
+
+                if let Some(overlap) = push_overlap {
+                    push_events.send(PushEvent {
+                        entity: attacker.entity,
+                        other: defender.entity,
+                        overlap,
+                    });
+                }
+            }
+        }
+    }
+}
+
+/// Separates overlapping characters, each one moving half the overlap away from the other.
+fn resolve_push_contacts(
+    mut push_events: EventReader<PushEvent>,
+    mut query: Query<&mut Position, With<CollisionBody>>,
+) {
+    for event in push_events.read() {
+        let Ok([mut position, mut other_position]) = query.get_many_mut([event.entity, event.other]) else { continue; };
+
+        let direction = if position.0.x <= other_position.0.x { -1 } else { 1 };
+        let push = (to_subpixels(event.overlap) + 1) / 2;
+        position.0.x += direction * push;
+        other_position.0.x -= direction * push;
+    }
+}
+
+fn log_contacts(
+    mut hit_events: EventReader<HitEvent>,
+    mut throw_events: EventReader<ThrowEvent>,
+) {
+    for event in hit_events.read() {
+        debug!("{:?} hit {:?} at {} for {} damage", event.attacker, event.defender, event.point, event.properties.damage);
+    }
+
+    for event in throw_events.read() {
+        debug!("{:?} threw {:?} for {} damage", event.attacker, event.defender, event.properties.damage);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+    use crate::core::core_core::GameMode;
+
+    fn rect(min: (f32, f32), max: (f32, f32)) -> Rect {
+        Rect::new(min.0, min.1, max.0, max.1)
+    }
+
+    fn world_box(rect: Rect, hit_group: u32) -> WorldBox {
+        WorldBox { rect, properties: EditorHitProperties { hit_group, ..default() } }
+    }
+
+    #[test]
+    fn overlap_needs_a_shared_area() {
+        let a = rect((0.0, 0.0), (10.0, 10.0));
+
+        assert_eq!(overlap(&a, &rect((10.0, 0.0), (20.0, 10.0))), None, "touching edges");
+        assert_eq!(overlap(&a, &rect((10.0, 10.0), (20.0, 20.0))), None, "touching corners");
+        assert_eq!(overlap(&a, &rect((5.0, 5.0), (5.0, 5.0))), None, "zero size inside");
+        assert_eq!(overlap(&a, &rect((5.0, 0.0), (5.0, 10.0))), None, "zero width inside");
+        assert_eq!(overlap(&a, &rect((20.0, 0.0), (30.0, 10.0))), None, "apart");
+
+        let inner = rect((2.0, 3.0), (4.0, 5.0));
+        assert_eq!(overlap(&a, &inner), Some(inner), "contained");
+        assert_eq!(overlap(&inner, &a), Some(inner), "containing");
+        assert_eq!(overlap(&a, &rect((8.0, -5.0), (12.0, 5.0))), Some(rect((8.0, 0.0), (10.0, 5.0))));
+    }
+
+    #[test]
+    fn first_contacts_keep_box_order_and_one_contact_per_group() {
+        let target = [world_box(rect((0.0, 0.0), (10.0, 10.0)), 0)];
+        let attack = [
+            world_box(rect((20.0, 0.0), (30.0, 10.0)), 2),
+            world_box(rect((5.0, 0.0), (15.0, 10.0)), 2),
+            world_box(rect((8.0, 0.0), (15.0, 10.0)), 2),
+            world_box(rect((6.0, 0.0), (15.0, 10.0)), 1),
+            world_box(rect((9.0, 0.0), (15.0, 10.0)), 3),
+        ];
+
+        let contacts = first_contacts(&attack, &target);
+        let groups: Vec<u32> = contacts.iter().map(|(properties, _)| properties.hit_group).collect();
+        assert_eq!(groups, [2, 1, 3]);
+        assert_eq!(contacts[0].1, rect((5.0, 0.0), (10.0, 10.0)), "first overlapping box of the group wins");
+    }
+
+    #[test]
+    fn hit_tracker_connects_each_group_once() {
+        let mut world = World::new();
+        let defender = world.spawn_empty().id();
+        let other_defender = world.spawn_empty().id();
+        let mut tracker = HitTracker::default();
+
+        assert!(tracker.try_connect(defender, 0));
+        assert!(!tracker.try_connect(defender, 0), "same group");
+        assert!(tracker.try_connect(defender, 1), "other group");
+        assert!(tracker.try_connect(other_defender, 0), "other defender");
+        assert_eq!(tracker.connected(), [(defender, 0), (defender, 1), (other_defender, 0)]);
+
+        tracker.clear();
+        assert!(tracker.connected().is_empty());
+        assert!(tracker.try_connect(defender, 0));
+    }
+
+    /// Sheet with an attacking frame whose hit box reaches 10 pixels forward, a recovery frame without boxes,
+    /// a standing frame with a hurt box and an attacking frame that can be hit.
+    fn collision_world() -> World {
+        let mut attack = EditorFrameData::empty();
+        attack.push_box(EditorBoxKind::Hit, Vec2::new(10.0, 10.0), Vec2::new(10.0, 0.0));
+        let recovery = EditorFrameData::empty();
+        let mut stand = EditorFrameData::empty();
+        stand.push_box(EditorBoxKind::Hurt, Vec2::new(10.0, 10.0), Vec2::ZERO);
+        let mut exposed_attack = attack.clone();
+        exposed_attack.push_box(EditorBoxKind::Hurt, Vec2::new(10.0, 10.0), Vec2::ZERO);
+
+        let mut world = World::new();
+        world.insert_resource(GameState { mode: GameMode::Game, requested_mode: None });
+        world.insert_resource(EditorSpriteSheets::from_sheet_infos(vec![
+            EditorSpriteSheetInfo::from_frames("test", vec![attack, recovery, stand, exposed_attack]),
+        ]));
+        world.init_resource::<Events<HitEvent>>();
+        world.init_resource::<Events<ThrowEvent>>();
+        world.init_resource::<Events<PushEvent>>();
+        world
+    }
+
+    fn spawn_body(world: &mut World, frame: usize, x: f32) -> Entity {
+        world.spawn((
+            Position::from_world(Vec2::new(x, 0.0)),
+            CollisionBody { sheet_id: "test".to_string(), frame, scale: 1.0 },
+            Facing::Right,
+            HitTracker::default(),
+        )).id()
+    }
+
+    fn detect_hits(world: &mut World) -> usize {
+        world.run_system_once(detect_collisions);
+        let mut events = world.resource_mut::<Events<HitEvent>>();
+        let hits = events.drain().count();
+        hits
+    }
+
+    #[test]
+    fn attacks_hit_once_until_a_frame_without_hit_boxes() {
+        let mut world = collision_world();
+        let attacker = spawn_body(&mut world, 0, 0.0);
+        spawn_body(&mut world, 2, 12.0);
+
+        assert_eq!(detect_hits(&mut world), 1);
+        assert_eq!(detect_hits(&mut world), 0, "the same attack already connected");
+
+        world.get_mut::<CollisionBody>(attacker).unwrap().frame = 1;
+        assert_eq!(detect_hits(&mut world), 0);
+        assert!(world.get::<HitTracker>(attacker).unwrap().connected().is_empty(), "recovery frame ends the attack");
+
+        world.get_mut::<CollisionBody>(attacker).unwrap().frame = 0;
+        assert_eq!(detect_hits(&mut world), 1, "a new attack connects again");
+    }
+
+    #[test]
+    fn trades_are_resolved_in_player_order() {
+        let mut world = collision_world();
+        let second = spawn_body(&mut world, 3, 12.0);
+        world.entity_mut(second).insert((Player { index: 1 }, Facing::Left));
+        let first = spawn_body(&mut world, 3, 0.0);
+        world.entity_mut(first).insert(Player { index: 0 });
+        let unowned = spawn_body(&mut world, 3, 24.0);
+        world.entity_mut(unowned).insert(Facing::Left);
+        assert!(second < first, "spawned out of player order");
+
+        world.run_system_once(detect_collisions);
+        let attackers: Vec<Entity> = world.resource_mut::<Events<HitEvent>>().drain().map(|event| event.attacker).collect();
+        assert_eq!(attackers, [first, second, unowned]);
+    }
+}
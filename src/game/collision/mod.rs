@@ -0,0 +1,5 @@
+pub mod collision_core;
+
+use crate::editor::editor_core::*;
+
+use bevy::prelude::*;
@@ -1,46 +1,115 @@
 use bevy::app::{App, Update};
 use bevy::math::Vec3;
+use bevy::utils::HashMap;
 use bevy::sprite::MaterialMesh2dBundle;
 use bevy_math::primitives::Rectangle;
+use serde::{Deserialize, Serialize};
 
 use crate::core::core_core::*;
 use crate::core::core_gui::*;
 use crate::editor::editor_core::*;
-use crate::editor::inspector::inspector_core::SelectedFrame;
 use crate::game::*;
+use crate::game::character::character_core::*;
+use crate::game::collision::collision_core::*;
 use crate::game::game_gui::*;
+use crate::game::input::input_core::{InputHistory, InputPlugin};
+use crate::game::moves::moves_core::{MoveList, MovesPlugin};
+use crate::game::netplay::netplay_core::NetplayPlugin;
+use crate::game::replay::replay_core::{begin_match, ReplayPlugin};
+use crate::game::round::round_core::{update_round, Health, RoundPlugin, RoundStartEvent, RoundState};
+use crate::game::simulation::simulation_core::*;
+use crate::game::training::training_core::TrainingPlugin;
 
 pub struct GamePlugin;
 
 impl Plugin for GamePlugin {
     fn build(&self, app: &mut App) {
         app.add_plugins(GameGuiPlugin)
+            .add_plugins(CollisionPlugin)
+            .add_plugins(CharacterPlugin)
+            .add_plugins(SimulationPlugin)
+            .add_plugins(InputPlugin)
+            .add_plugins(MovesPlugin)
+            .add_plugins(RoundPlugin)
+            .add_plugins(ReplayPlugin)
+            .add_plugins(NetplayPlugin)
+            .add_plugins(TrainingPlugin)
             .insert_resource(GameCameraEntity::default())
-            .insert_resource(HitboxMeshAndMaterial::default())
-            .insert_resource(HurtboxMeshAndMaterial::default())
-            .add_systems(Update, animate_sprite)
-            .add_systems(Update, game_state_adapter_system)
+            .insert_resource(BoxMeshAndMaterials::default())
+            .insert_resource(VersusSettings::default())
+            .add_systems(SimulationSchedule, (face_opponents, animate_sprite).chain().in_set(SimulationSet::Step))
+            .add_systems(SimulationSchedule, restart_round.after(update_round).in_set(SimulationSet::Reaction))
+            .add_systems(Update, game_state_adapter_system.after(begin_match))
+            .add_systems(Update, sync_sprites)
             .add_systems(Update, gizmos_selected_sprite)
             .add_systems(Update, update_lifetimes);
     }
 }
 
+/// A versus character, `index` 0 is player one.
 #[derive(Component)]
-struct Player;
+pub struct Player {
+    pub index: usize,
+}
 
-#[derive(Default, Component)]
-struct GameCamera;
+/// Who drives a character.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Component, Serialize, Deserialize)]
+pub enum Controller {
+    Human,
+    Idle,
+    /// Presses random inputs drawn from the simulation RNG.
+    Random,
+}
 
-#[derive(Default, Resource)]
-struct HitboxMeshAndMaterial {
-    mesh: Handle<Mesh>,
-    material: Handle<ColorMaterial>,
+impl Controller {
+    pub const ALL: [Controller; 3] = [Controller::Human, Controller::Idle, Controller::Random];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Controller::Human => "Human",
+            Controller::Idle => "Idle",
+            Controller::Random => "Random",
+        }
+    }
+}
+
+/// Setup of one side of a versus match.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlayerSettings {
+    /// Sheet the character starts with until its first state change, its idle animation when `None`.
+    pub sheet_id: Option<String>,
+    pub start_position: Vec2,
+    pub controller: Controller,
 }
 
+/// Setup applied when game mode starts.
+#[derive(Debug, Clone, PartialEq, Resource, Serialize, Deserialize)]
+pub struct VersusSettings {
+    pub players: [PlayerSettings; 2],
+    /// Rounds a player has to win to take the match.
+    pub rounds_to_win: u32,
+}
+
+impl Default for VersusSettings {
+    fn default() -> Self {
+        VersusSettings {
+            players: [
+                PlayerSettings { sheet_id: None, start_position: Vec2::new(-250.0, 0.0), controller: Controller::Human },
+                PlayerSettings { sheet_id: None, start_position: Vec2::new(250.0, 0.0), controller: Controller::Idle },
+            ],
+            rounds_to_win: 2,
+        }
+    }
+}
+
+#[derive(Default, Component)]
+struct GameCamera;
+
+/// Shared box mesh with one material per box kind.
 #[derive(Default, Resource)]
-struct HurtboxMeshAndMaterial {
+struct BoxMeshAndMaterials {
     mesh: Handle<Mesh>,
-    material: Handle<ColorMaterial>,
+    materials: HashMap<EditorBoxKind, Handle<ColorMaterial>>,
 }
 
 #[derive(Component)]
@@ -53,41 +122,29 @@ pub struct GameCameraEntity {
     pub entity: Option<Entity>,
 }
 
-#[derive(Component)]
-struct AnimationIndices {
-    first: usize,
-    last: usize,
+/// Clip currently played by a sprite, one frame tick per simulation tick.
+#[derive(Debug, Clone, Component)]
+pub(crate) struct SpriteAnimation {
+    pub(crate) sheet_id: String,
+    pub(crate) playback: ClipPlayback,
 }
 
-#[derive(Component, Deref, DerefMut)]
-struct AnimationTimer(Timer);
-
-#[derive(Resource)]
-pub struct GameSelectedSpriteSheet {
-    pub id: Option<String>,
-    pub frame_index: Option<usize>,
-}
-
-impl Default for GameSelectedSpriteSheet {
-    fn default() -> Self {
-        GameSelectedSpriteSheet {
-            id: None,
-            frame_index: Some(0),
-        }
-    }
-}
+/// World units per sprite pixel of characters.
+const CHARACTER_SCALE: f32 = 6.0;
 
+#[allow(clippy::too_many_arguments)]
 fn game_state_adapter_system(
     mut commands: Commands,
     config_store: ResMut<GizmoConfigStore>,
     game_state: Res<GameState>,
     sprite_sheets: ResMut<EditorSpriteSheets>,
-    selected_frame: ResMut<SelectedFrame>,
+    character_animations: Res<CharacterAnimations>,
+    versus_settings: Res<VersusSettings>,
+    simulation_tick: ResMut<SimulationTick>,
     mut game_camera_entity: ResMut<GameCameraEntity>,
     meshes: ResMut<Assets<Mesh>>,
     materials: ResMut<Assets<ColorMaterial>>,
-    hit_box_mesh_and_material: ResMut<HitboxMeshAndMaterial>,
-    hurt_box_mesh_and_material: ResMut<HurtboxMeshAndMaterial>,
+    box_mesh_and_materials: ResMut<BoxMeshAndMaterials>,
     mut query: Query<Entity, With<Player>>) {
     match &game_state.mode {
         GameMode::Editor => {
@@ -100,63 +157,114 @@ fn game_state_adapter_system(
             }
         }
 
-        GameMode::Game => {
+        GameMode::Game | GameMode::Replay | GameMode::Netplay | GameMode::Training => {
             if game_camera_entity.entity.is_none() {
                 setup(
                     commands,
                     config_store,
                     sprite_sheets,
-                    selected_frame,
+                    character_animations,
+                    versus_settings,
+                    simulation_tick,
                     game_camera_entity,
-                    meshes, materials, hit_box_mesh_and_material, hurt_box_mesh_and_material);
+                    meshes, materials, box_mesh_and_materials);
             }
         }
     }
 }
 
 
+/// Steps the state machine of characters and advances animations by one tick.
+#[allow(clippy::type_complexity)]
 fn animate_sprite(
-    time: Res<Time>,
     game_state: Res<GameState>,
-    mut query: Query<(&AnimationIndices, &mut AnimationTimer, &mut TextureAtlas)>,
+    sprite_sheets: Res<EditorSpriteSheets>,
+    character_animations: Res<CharacterAnimations>,
+    move_list: Res<MoveList>,
+    mut query: Query<(
+        &mut SpriteAnimation,
+        &mut CollisionBody,
+        &mut Position,
+        Option<(&mut CharacterStateMachine, &CharacterInput, &Facing)>,
+    )>,
 ) {
-    if game_state.mode != GameMode::Game {
+    if !game_state.mode.is_simulated() {
         return;
     }
 
-    for (indices, mut timer, mut atlas) in &mut query {
-        timer.tick(time.delta());
-        if timer.just_finished() {
-            atlas.index = if atlas.index == indices.last {
-                indices.first
-            } else {
-                atlas.index + 1
-            };
+    for (mut animation, mut body, mut position, character) in &mut query {
+        let mut restarted = false;
+        let mut frozen = false;
+
+        if let Some((mut state_machine, input, facing)) = character {
+            frozen = state_machine.is_frozen();
+            state_machine.step(input, *facing, &mut position.0, animation.playback.finished);
+
+            let state_animation = character_animations.animation(state_machine.state, &move_list);
+            let sheet_info = state_animation.as_ref().and_then(|state_animation| sprite_sheets.sheet_info(&state_animation.sheet_id));
+            if let (true, Some(state_animation), Some(sheet_info)) = (state_machine.take_entered(), &state_animation, sheet_info) {
+                animation.sheet_id = state_animation.sheet_id.clone();
+                animation.playback = ClipPlayback::new(state_animation.clip(sheet_info));
+                restarted = true;
+            }
+        }
+
+        if !restarted && !frozen {
+            let Some(sheet_info) = sprite_sheets.sheet_info(&animation.sheet_id) else { continue; };
+            animation.playback.tick(sheet_info);
+        }
+
+        if body.sheet_id != animation.sheet_id {
+            body.sheet_id = animation.sheet_id.clone();
+        }
+        body.frame = animation.playback.frame;
+    }
+}
+
+/// Shows the simulated animation frame and facing of sprites.
+#[allow(clippy::type_complexity)]
+fn sync_sprites(
+    sprite_sheets: Res<EditorSpriteSheets>,
+    mut query: Query<(&SpriteAnimation, Option<&Facing>, &mut TextureAtlas, &mut Handle<Image>, &mut Sprite)>,
+) {
+    for (animation, facing, mut atlas, mut texture, mut sprite) in &mut query {
+        let Some(sheet_atlas) = sprite_sheets.sheets.get(&animation.sheet_id) else { continue; };
+
+        if atlas.layout != sheet_atlas.handle {
+            atlas.layout = sheet_atlas.handle.clone();
+            *texture = sheet_atlas.texture_handle.clone();
         }
+        atlas.index = animation.playback.frame;
+        sprite.flip_x = facing.is_some_and(|facing| *facing == Facing::Left);
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn setup(
     mut commands: Commands,
     mut config_store: ResMut<GizmoConfigStore>,
-    mut sprite_sheets: ResMut<EditorSpriteSheets>,
-    selected_frame: ResMut<SelectedFrame>,
+    sprite_sheets: ResMut<EditorSpriteSheets>,
+    character_animations: Res<CharacterAnimations>,
+    versus_settings: Res<VersusSettings>,
+    mut simulation_tick: ResMut<SimulationTick>,
     mut game_camera_entity: ResMut<GameCameraEntity>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
-    mut hit_box_mesh_and_material: ResMut<HitboxMeshAndMaterial>,
-    mut hurtbox_mesh_and_material: ResMut<HurtboxMeshAndMaterial>,
+    mut box_mesh_and_materials: ResMut<BoxMeshAndMaterials>,
 ) {
     let (config, _) = config_store.config_mut::<DefaultGizmoConfigGroup>();
     config.line_width = 5.;
 
-    // hitbox gizmo
-    hit_box_mesh_and_material.mesh = meshes.add(Mesh::from(Rectangle::default()));
-    hit_box_mesh_and_material.material = materials.add(ColorMaterial::from(Color::rgb_u8(243, 139, 168)));
-
-    // hurtbox gizmo
-    hurtbox_mesh_and_material.mesh = meshes.add(Mesh::from(Rectangle::default()));
-    hurtbox_mesh_and_material.material = materials.add(ColorMaterial::from(Color::rgb_u8(166, 227, 161)));
+    // box gizmos
+    box_mesh_and_materials.mesh = meshes.add(Mesh::from(Rectangle::default()));
+    for kind in EditorBoxKind::ALL {
+        let color = match kind {
+            EditorBoxKind::Hit => Color::rgb_u8(243, 139, 168),
+            EditorBoxKind::Hurt => Color::rgb_u8(166, 227, 161),
+            _ => kind.color(),
+        };
+        box_mesh_and_materials.materials.insert(kind, materials.add(ColorMaterial::from(color)));
+    }
 
     // camera
     let mut entity = commands.spawn(Camera2dBundle {
@@ -169,103 +277,179 @@ fn setup(
     entity.insert(GameCamera);
     game_camera_entity.entity = Some(entity.id());
 
-    if let Some(id) = &selected_frame.sprite_sheet_id {
-        if let Some(sprite_sheet_atlas) = sprite_sheets.sheets.get_mut(id) {
-            let animation_indices = AnimationIndices { first: 1, last: sprite_sheet_atlas.sprite_sheet_info.columns - 1 };
-            let texture_handle = sprite_sheet_atlas.texture_handle.clone();
-            let mut player_entity = commands.spawn(
-                (SpriteSheetBundle {
-                    texture: texture_handle,
-                    atlas: TextureAtlas {
-                        layout: sprite_sheet_atlas.handle.clone(),
-                        index: animation_indices.first,
-                    },
-                    transform: Transform::from_scale(Vec3::splat(6.0)),
+    *simulation_tick = SimulationTick::default();
+
+    commands.insert_resource(RoundState::new(versus_settings.rounds_to_win));
+    spawn_characters(&mut commands, &sprite_sheets, &character_animations, &versus_settings, &HashMap::new());
+}
+
+/// Spawns both characters at their start positions, idle and at full health.
+/// Characters already in `entities`, by player index, are reset in place and keep their entity.
+pub(crate) fn spawn_characters(
+    commands: &mut Commands,
+    sprite_sheets: &EditorSpriteSheets,
+    character_animations: &CharacterAnimations,
+    versus_settings: &VersusSettings,
+    entities: &HashMap<usize, Entity>,
+) {
+    let [first, second] = &versus_settings.players;
+    for (index, (settings, opponent)) in [(first, second), (second, first)].into_iter().enumerate() {
+        let idle_animation = character_animations.states.get(&CharacterState::Idle);
+        let Some(id) = settings.sheet_id.as_ref()
+            .or(idle_animation.map(|idle_animation| &idle_animation.sheet_id)) else { continue; };
+        let Some(sprite_sheet_atlas) = sprite_sheets.sheets.get(id) else { continue; };
+
+        let sheet_info = &sprite_sheet_atlas.sprite_sheet_info;
+        let clip = match (&settings.sheet_id, idle_animation) {
+            (None, Some(idle_animation)) => idle_animation.clip(sheet_info),
+            _ => sheet_info.default_clip(),
+        };
+        let animation = SpriteAnimation {
+            sheet_id: id.clone(),
+            playback: ClipPlayback::new(clip),
+        };
+        let facing = Facing::toward(settings.start_position.x, opponent.start_position.x);
+
+        let mut entity = match entities.get(&index) {
+            Some(entity) => commands.entity(*entity),
+            None => commands.spawn_empty(),
+        };
+        entity.insert((
+            SpriteSheetBundle {
+                sprite: Sprite {
+                    flip_x: facing == Facing::Left,
                     ..default()
                 },
-                 animation_indices,
-                 AnimationTimer(Timer::from_seconds(0.2, TimerMode::Repeating))
-                ));
+                texture: sprite_sheet_atlas.texture_handle.clone(),
+                atlas: TextureAtlas {
+                    layout: sprite_sheet_atlas.handle.clone(),
+                    index: animation.playback.frame,
+                },
+                transform: Transform::from_translation(settings.start_position.extend(index as f32))
+                    .with_scale(Vec3::splat(CHARACTER_SCALE)),
+                ..default()
+            },
+            Position::from_world(settings.start_position),
+            CollisionBody { sheet_id: id.clone(), frame: animation.playback.frame, scale: CHARACTER_SCALE },
+            animation,
+        )).insert((
+            Player { index },
+            settings.controller,
+            facing,
+            HitTracker::default(),
+            CharacterStateMachine::default(),
+            CharacterInput::default(),
+            InputHistory::default(),
+            Health::default(),
+        ));
+    }
+}
 
-            player_entity.insert(Player);
-        }
+/// Puts the characters back at their start positions when a round starts.
+/// They keep their entities, so snapshots taken before the restart still apply to them.
+fn restart_round(
+    mut commands: Commands,
+    mut round_start_events: EventReader<RoundStartEvent>,
+    sprite_sheets: Res<EditorSpriteSheets>,
+    character_animations: Res<CharacterAnimations>,
+    versus_settings: Res<VersusSettings>,
+    query: Query<(Entity, &Player)>,
+) {
+    if round_start_events.read().count() == 0 {
+        return;
     }
+
+    let entities = query.iter().map(|(entity, player)| (player.index, entity)).collect();
+    spawn_characters(&mut commands, &sprite_sheets, &character_animations, &versus_settings, &entities);
+}
+
+/// Starts the match over from its initial state, for seeking back in a replay or switching between game and replay.
+pub(crate) fn restart_match(
+    mut commands: Commands,
+    sprite_sheets: Res<EditorSpriteSheets>,
+    character_animations: Res<CharacterAnimations>,
+    versus_settings: Res<VersusSettings>,
+    mut simulation_tick: ResMut<SimulationTick>,
+    mut frame_advantage: ResMut<FrameAdvantage>,
+    query: Query<(Entity, &Player)>,
+) {
+    *simulation_tick = SimulationTick::default();
+    *frame_advantage = FrameAdvantage::default();
+    commands.insert_resource(RoundState::new(versus_settings.rounds_to_win));
+
+    let entities = query.iter().map(|(entity, player)| (player.index, entity)).collect();
+    spawn_characters(&mut commands, &sprite_sheets, &character_animations, &versus_settings, &entities);
 }
 
 fn gizmos_selected_sprite(
     mut commands: Commands,
     mut gizmos: Gizmos,
-    hitbox_mesh_and_material: Res<HitboxMeshAndMaterial>,
-    hurtbox_mesh_and_material: Res<HurtboxMeshAndMaterial>,
-    selected_frame: ResMut<SelectedFrame>,
-    mut sprite_sheets: ResMut<EditorSpriteSheets>,
-    mut query: Query<(&Transform, &AnimationIndices, &mut AnimationTimer, &mut TextureAtlas)>,
+    box_mesh_and_materials: Res<BoxMeshAndMaterials>,
+    sprite_sheets: Res<EditorSpriteSheets>,
+    query: Query<(&Position, &CollisionBody, Option<&Facing>)>,
     game_state: Res<GameState>,
     gui_state: ResMut<CoreGuiState>,
 ) {
-    if game_state.mode != GameMode::Game {
+    if !game_state.mode.is_simulated() {
         return;
     }
 
-    if let Some(id) = &selected_frame.sprite_sheet_id {
-        if let Some(sprite_sheet_atlas) = sprite_sheets.sheets.get_mut(id) {
-            for (transform, _indices, _timer, atlas) in &mut query {
-                let scale = transform.scale.truncate();
-
-                if let Some(frame_data) = sprite_sheet_atlas.sprite_sheet_info.frames.get_mut(atlas.index) {
-                    if gui_state.show_hit_boxes {
-                        for hit_box in &frame_data.hit_boxes {
-                            let hit_box_size_scaled = hit_box.size * scale;
-                            let hit_box_offset_scaled = hit_box.offset * scale;
-
-                            commands.spawn((MaterialMesh2dBundle {
-                                mesh: hitbox_mesh_and_material.mesh.clone().into(),
-                                material: hitbox_mesh_and_material.material.clone(),
-                                transform: Transform::from_translation(hit_box_offset_scaled.extend(100.))
-                                    .with_scale(hit_box_size_scaled.extend(0.)),
-                                ..default()
-                            }, Lifetime {
-                                timer: Timer::from_seconds(0.01, TimerMode::Once),
-                            }));
-
-                            gizmos.rect_2d(
-                                transform.translation.truncate() + hit_box_offset_scaled,
-                                0.0,
-                                hit_box_size_scaled,
-                                Color::rgba(1f32, 0f32, 0f32, 0.3f32),
-                            );
-                        }
-                    }
-
-                    if gui_state.show_hurt_boxes {
-                        for hurt_box in &frame_data.hurt_boxes {
-                            let hurt_box_size_scaled = hurt_box.size * scale;
-                            let hurt_box_offset_scaled = hurt_box.offset * scale;
-
-                            commands.spawn((MaterialMesh2dBundle {
-                                mesh: hurtbox_mesh_and_material.mesh.clone().into(),
-                                material: hurtbox_mesh_and_material.material.clone(),
-                                transform: Transform::from_translation(hurt_box_offset_scaled.extend(100.))
-                                    .with_scale(hurt_box_size_scaled.extend(0.)),
-                                ..default()
-                            }, Lifetime {
-                                timer: Timer::from_seconds(0.01, TimerMode::Once),
-                            }));
-
-                            gizmos.rect_2d(
-                                transform.translation.truncate() + hurt_box_offset_scaled,
-                                0.0,
-                                hurt_box_size_scaled,
-                                Color::rgba(0f32, 1f32, 0f32, 0.3f32),
-                            );
-                        }
-                    }
-                }
+    for (position, body, facing) in &query {
+        let Some(sprite_sheet_info) = sprite_sheets.sheet_info(&body.sheet_id) else { continue; };
+        let Some(frame_data) = sprite_sheet_info.frames.get(body.frame) else { continue; };
+        let facing = facing.copied().unwrap_or_default();
+
+        for kind in EditorBoxKind::ALL.into_iter().filter(|kind| gui_state.shows_boxes(*kind)) {
+            let Some(material) = box_mesh_and_materials.materials.get(&kind) else { continue; };
+
+            for world_box in world_boxes(frame_data, kind, position.to_world(), body.scale, facing) {
+                commands.spawn((MaterialMesh2dBundle {
+                    mesh: box_mesh_and_materials.mesh.clone().into(),
+                    material: material.clone(),
+                    transform: Transform::from_translation(world_box.rect.center().extend(100.))
+                        .with_scale(world_box.rect.size().extend(0.)),
+                    ..default()
+                }, Lifetime {
+                    timer: Timer::from_seconds(0.01, TimerMode::Once),
+                }));
+
+                gizmos.rect_2d(
+                    world_box.rect.center(),
+                    0.0,
+                    world_box.rect.size(),
+                    kind.color().with_a(0.3),
+                );
             }
         }
     }
 }
 
+/// Turns every character toward the closest other character, characters busy with an action keep their facing.
+fn face_opponents(
+    game_state: Res<GameState>,
+    mut query: Query<(Entity, &Position, &mut Facing, Option<&CharacterStateMachine>), With<CollisionBody>>,
+) {
+    if !game_state.mode.is_simulated() {
+        return;
+    }
+
+    let positions: Vec<(Entity, i32)> = query.iter()
+        .map(|(entity, position, ..)| (entity, position.0.x))
+        .collect();
+
+    for (entity, position, mut facing, state_machine) in &mut query {
+        let x = position.0.x;
+        let closest = positions.iter()
+            .filter(|(other, other_x)| *other != entity && *other_x != x)
+            .min_by_key(|(other, other_x)| ((other_x - x).abs(), *other));
+
+        let can_turn = state_machine.is_none_or(|state_machine| state_machine.state.is_actionable());
+        if let (Some((_, other_x)), true) = (closest, can_turn) {
+            *facing = Facing::toward(x, *other_x);
+        }
+    }
+}
+
 fn update_lifetimes(
     mut commands: Commands,
     time: Res<Time>,
@@ -278,4 +462,52 @@ fn update_lifetimes(
             commands.entity(entity).despawn();
         }
     }
-}
\ No newline at end of file
+}
+
+/// Sheet of three frames a character can be hit and pushed on, attacks hit on their second frame.
+#[cfg(test)]
+fn test_character_sheet(id: &str) -> EditorSpriteSheetInfo {
+    let frames = (0..3).map(|frame_index| {
+        let mut frame = EditorFrameData::empty();
+        frame.push_box(EditorBoxKind::Hurt, Vec2::new(8.0, 16.0), Vec2::ZERO);
+        frame.push_box(EditorBoxKind::Push, Vec2::new(6.0, 16.0), Vec2::ZERO);
+        if id.contains("punch") && frame_index == 1 {
+            frame.push_box(EditorBoxKind::Hit, Vec2::new(8.0, 4.0), Vec2::new(8.0, 4.0));
+        }
+        frame
+    }).collect();
+    EditorSpriteSheetInfo::from_frames(id, frames)
+}
+
+/// World of a match in `mode` between two characters standing close enough to fight, without rendering.
+/// Sheets of every state animation are stand-ins from `test_character_sheet`.
+#[cfg(test)]
+pub(crate) fn test_match_world(mode: GameMode, controller: Controller) -> World {
+    use bevy::ecs::system::RunSystemOnce;
+
+    let mut app = App::new();
+    app.add_plugins(GamePlugin)
+        .insert_resource(GameState { mode, requested_mode: None })
+        .init_resource::<ButtonInput<KeyCode>>()
+        .init_resource::<ButtonInput<GamepadButton>>()
+        .init_resource::<Axis<GamepadAxis>>();
+
+    let mut sheet_ids: Vec<String> = app.world.resource::<CharacterAnimations>().states.values()
+        .map(|animation| animation.sheet_id.clone())
+        .collect();
+    sheet_ids.sort();
+    sheet_ids.dedup();
+    app.insert_resource(EditorSpriteSheets::from_sheet_infos(sheet_ids.iter().map(|id| test_character_sheet(id)).collect()));
+
+    let mut versus_settings = VersusSettings::default();
+    for (player, x) in versus_settings.players.iter_mut().zip([-10.0, 10.0]) {
+        player.start_position.x = x;
+        player.controller = controller;
+    }
+    app.insert_resource(versus_settings);
+
+    let mut world = std::mem::take(&mut app.world);
+    world.insert_resource(SimulationRng::new(7));
+    world.run_system_once(restart_match);
+    world
+}
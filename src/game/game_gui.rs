@@ -1,9 +1,100 @@
+pub use bevy::prelude::*;
 use bevy::app::App;
 use bevy::prelude::Plugin;
+use bevy_egui::{egui, EguiContexts};
+
+use crate::core::core_core::{GameMode, GameState};
+use crate::editor::editor_core::{EditorClipLoopMode, EditorSpriteSheets};
+use crate::game::character::character_core::{CharacterAnimations, CharacterState};
+use crate::game::game_core::{Controller, VersusSettings};
 
 pub struct GameGuiPlugin;
 
 impl Plugin for GameGuiPlugin {
-    fn build(&self, _app: &mut App) {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, display_versus_settings);
+    }
+}
+
+/// Versus setup, changes apply the next time game mode starts.
+fn display_versus_settings(
+    mut egui_contexts: EguiContexts,
+    mut versus_settings: ResMut<VersusSettings>,
+    mut character_animations: ResMut<CharacterAnimations>,
+    sprite_sheets: Res<EditorSpriteSheets>,
+    game_state: Res<GameState>,
+) {
+    if game_state.mode != GameMode::Editor {
+        return;
     }
-}
\ No newline at end of file
+
+    let ctx = egui_contexts.ctx_mut();
+
+    egui::Window::new("Versus").default_open(false).show(ctx, |ui| {
+        for (index, settings) in versus_settings.players.iter_mut().enumerate() {
+            ui.push_id(index, |ui| {
+                ui.strong(format!("Player {}", index + 1));
+
+                egui::Grid::new("player_settings").num_columns(2).show(ui, |ui| {
+                    ui.label("Sheet");
+                    egui::ComboBox::from_id_source("sheet")
+                        .selected_text(settings.sheet_id.as_deref().unwrap_or("Idle animation"))
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(&mut settings.sheet_id, None, "Idle animation");
+                            for id in &sprite_sheets.order {
+                                ui.selectable_value(&mut settings.sheet_id, Some(id.clone()), id);
+                            }
+                        });
+                    ui.end_row();
+
+                    ui.label("Start");
+                    ui.horizontal(|ui| {
+                        ui.add(egui::DragValue::new(&mut settings.start_position.x).prefix("x: "));
+                        ui.add(egui::DragValue::new(&mut settings.start_position.y).prefix("y: "));
+                    });
+                    ui.end_row();
+
+                    ui.label("Controller");
+                    egui::ComboBox::from_id_source("controller")
+                        .selected_text(settings.controller.label())
+                        .show_ui(ui, |ui| {
+                            for controller in Controller::ALL {
+                                ui.selectable_value(&mut settings.controller, controller, controller.label());
+                            }
+                        });
+                    ui.end_row();
+                });
+            });
+        }
+
+        ui.horizontal(|ui| {
+            ui.label("Rounds to win");
+            ui.add(egui::DragValue::new(&mut versus_settings.rounds_to_win).clamp_range(1..=5));
+        });
+
+        egui::CollapsingHeader::new("Animations").show(ui, |ui| {
+            egui::Grid::new("state_animations").num_columns(3).show(ui, |ui| {
+                for state in CharacterState::ALL {
+                    let Some(state_animation) = character_animations.states.get_mut(&state) else { continue; };
+
+                    ui.label(state.label());
+                    egui::ComboBox::from_id_source((state, "sheet"))
+                        .selected_text(state_animation.sheet_id.as_str())
+                        .show_ui(ui, |ui| {
+                            for id in &sprite_sheets.order {
+                                ui.selectable_value(&mut state_animation.sheet_id, id.clone(), id);
+                            }
+                        });
+                    egui::ComboBox::from_id_source((state, "loop_mode"))
+                        .selected_text(state_animation.loop_mode.label())
+                        .show_ui(ui, |ui| {
+                            for loop_mode in EditorClipLoopMode::ALL {
+                                ui.selectable_value(&mut state_animation.loop_mode, loop_mode, loop_mode.label());
+                            }
+                        });
+                    ui.end_row();
+                }
+            });
+        });
+    });
+}
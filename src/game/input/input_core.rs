@@ -0,0 +1,360 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::fs;
+
+use serde::{Deserialize, Serialize};
+
+use crate::core::core_core::{GameMode, GameState};
+use crate::game::input::*;
+use crate::game::input::input_gui::InputGuiPlugin;
+use crate::game::simulation::simulation_core::{SimulationSchedule, SimulationSet};
+
+pub const INPUT_BINDINGS_PATH: &str = "assets/input_bindings.json";
+
+/// How far a stick has to be pushed to count as a direction press.
+const STICK_DEAD_ZONE: f32 = 0.5;
+
+pub(crate) struct InputPlugin;
+
+impl Plugin for InputPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(InputGuiPlugin)
+            .insert_resource(load_input_bindings(INPUT_BINDINGS_PATH))
+            .insert_resource(PlayerInputs::default())
+            .add_systems(SimulationSchedule, sample_player_inputs.in_set(SimulationSet::Input));
+    }
+}
+
+/// Abstract fighting game buttons, what characters react to whatever device produced them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum InputButton {
+    Up,
+    Down,
+    Left,
+    Right,
+    LightPunch,
+    MediumPunch,
+    HeavyPunch,
+    LightKick,
+    MediumKick,
+    HeavyKick,
+}
+
+impl InputButton {
+    pub const ALL: [InputButton; 10] = [
+        InputButton::Up,
+        InputButton::Down,
+        InputButton::Left,
+        InputButton::Right,
+        InputButton::LightPunch,
+        InputButton::MediumPunch,
+        InputButton::HeavyPunch,
+        InputButton::LightKick,
+        InputButton::MediumKick,
+        InputButton::HeavyKick,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            InputButton::Up => "Up",
+            InputButton::Down => "Down",
+            InputButton::Left => "Left",
+            InputButton::Right => "Right",
+            InputButton::LightPunch => "LP",
+            InputButton::MediumPunch => "MP",
+            InputButton::HeavyPunch => "HP",
+            InputButton::LightKick => "LK",
+            InputButton::MediumKick => "MK",
+            InputButton::HeavyKick => "HK",
+        }
+    }
+
+    pub fn is_direction(&self) -> bool {
+        matches!(self, InputButton::Up | InputButton::Down | InputButton::Left | InputButton::Right)
+    }
+
+    fn bit(&self) -> u16 {
+        1 << *self as u16
+    }
+}
+
+/// Buttons held by a player on one tick, opposite directions cancel out.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct InputFrame(u16);
+
+impl InputFrame {
+    pub fn pressed(&self, button: InputButton) -> bool {
+        self.0 & button.bit() != 0
+    }
+
+    pub fn set(&mut self, button: InputButton, pressed: bool) {
+        if pressed {
+            self.0 |= button.bit();
+        } else {
+            self.0 &= !button.bit();
+        }
+    }
+
+    /// `-1` for left and `1` for right, in screen space.
+    pub fn horizontal(&self) -> i8 {
+        self.pressed(InputButton::Right) as i8 - self.pressed(InputButton::Left) as i8
+    }
+
+    /// `-1` for down and `1` for up.
+    pub fn vertical(&self) -> i8 {
+        self.pressed(InputButton::Up) as i8 - self.pressed(InputButton::Down) as i8
+    }
+
+    /// Direction in numpad notation relative to `facing_direction`, 6 is forward and 4 is back.
+    pub fn numpad(&self, facing_direction: i32) -> u8 {
+        let forward = self.horizontal() as i32 * facing_direction.signum();
+        (5 + forward + 3 * self.vertical() as i32) as u8
+    }
+
+    /// The same frame with left and right swapped, converts between screen space and facing relative inputs.
+    pub fn mirrored(&self) -> Self {
+        let mut frame = *self;
+        frame.set(InputButton::Left, self.pressed(InputButton::Right));
+        frame.set(InputButton::Right, self.pressed(InputButton::Left));
+        frame
+    }
+
+    /// Clears both directions of an axis when both are held.
+    fn cancel_opposites(mut self) -> Self {
+        for (negative, positive) in [(InputButton::Left, InputButton::Right), (InputButton::Down, InputButton::Up)] {
+            if self.pressed(negative) && self.pressed(positive) {
+                self.set(negative, false);
+                self.set(positive, false);
+            }
+        }
+        self
+    }
+}
+
+/// Number of distinct input changes kept by `InputHistory`.
+const INPUT_HISTORY_LENGTH: usize = 64;
+
+/// An input frame and for how many consecutive ticks it was held.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct InputHistoryEntry {
+    pub frame: InputFrame,
+    /// Direction of `frame` relative to the facing of the character when it was entered.
+    pub numpad: u8,
+    pub ticks: u32,
+}
+
+/// Recent inputs of a player, identical consecutive ticks are merged into one entry.
+#[derive(Debug, Default, Clone, Hash, Component)]
+pub struct InputHistory {
+    entries: VecDeque<InputHistoryEntry>,
+}
+
+impl InputHistory {
+    /// Appends the frame of the current tick, entered while facing `facing_direction`.
+    pub fn push(&mut self, frame: InputFrame, facing_direction: i32) {
+        let numpad = frame.numpad(facing_direction);
+        match self.entries.back_mut() {
+            Some(entry) if entry.frame == frame && entry.numpad == numpad => entry.ticks = entry.ticks.saturating_add(1),
+            _ => {
+                self.entries.push_back(InputHistoryEntry { frame, numpad, ticks: 1 });
+                if self.entries.len() > INPUT_HISTORY_LENGTH {
+                    self.entries.pop_front();
+                }
+            }
+        }
+    }
+
+    /// Entries from the newest to the oldest.
+    pub fn entries(&self) -> impl Iterator<Item = &InputHistoryEntry> {
+        self.entries.iter().rev()
+    }
+
+    /// One frame per tick from the current tick back, at most `ticks` of them.
+    pub fn ticks(&self, ticks: usize) -> impl Iterator<Item = InputFrame> + '_ {
+        self.entries()
+            .flat_map(|entry| std::iter::repeat_n(entry.frame, entry.ticks as usize))
+            .take(ticks)
+    }
+}
+
+/// A physical input that presses an `InputButton`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum InputBinding {
+    Key(KeyCode),
+    GamepadButton(GamepadButtonType),
+    /// A stick pushed past the dead zone toward `positive` or the negative side.
+    GamepadAxis { axis: GamepadAxisType, positive: bool },
+}
+
+impl InputBinding {
+    pub fn label(&self) -> String {
+        match self {
+            InputBinding::Key(key) => format!("{:?}", key),
+            InputBinding::GamepadButton(button) => format!("Pad {:?}", button),
+            InputBinding::GamepadAxis { axis, positive } => format!("Pad {:?}{}", axis, if *positive { "+" } else { "-" }),
+        }
+    }
+}
+
+/// Bindings of one player, keyboard keys always apply, gamepad ones only for `gamepad`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlayerBindings {
+    pub gamepad: Option<usize>,
+    pub buttons: BTreeMap<InputButton, Vec<InputBinding>>,
+}
+
+impl PlayerBindings {
+    pub fn bindings(&self, button: InputButton) -> &[InputBinding] {
+        self.buttons.get(&button).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn bindings_mut(&mut self, button: InputButton) -> &mut Vec<InputBinding> {
+        self.buttons.entry(button).or_default()
+    }
+
+    /// Reads the buttons currently held on the bound devices.
+    pub fn sample(
+        &self,
+        keyboard: &ButtonInput<KeyCode>,
+        gamepad_buttons: &ButtonInput<GamepadButton>,
+        gamepad_axes: &Axis<GamepadAxis>,
+    ) -> InputFrame {
+        let gamepad = self.gamepad.map(Gamepad::new);
+        let is_held = |binding: &InputBinding| match (*binding, gamepad) {
+            (InputBinding::Key(key), _) => keyboard.pressed(key),
+            (InputBinding::GamepadButton(button), Some(gamepad)) => gamepad_buttons.pressed(GamepadButton::new(gamepad, button)),
+            (InputBinding::GamepadAxis { axis, positive }, Some(gamepad)) => {
+                let value = gamepad_axes.get(GamepadAxis::new(gamepad, axis)).unwrap_or(0.0);
+                if positive { value > STICK_DEAD_ZONE } else { value < -STICK_DEAD_ZONE }
+            }
+            (_, None) => false,
+        };
+
+        let mut frame = InputFrame::default();
+        for button in InputButton::ALL {
+            frame.set(button, self.bindings(button).iter().any(is_held));
+        }
+        frame.cancel_opposites()
+    }
+}
+
+/// Bindings of both players, saved to `INPUT_BINDINGS_PATH` whenever they change.
+#[derive(Debug, Clone, PartialEq, Resource, Serialize, Deserialize)]
+pub struct InputBindings {
+    pub players: [PlayerBindings; 2],
+}
+
+impl Default for InputBindings {
+    fn default() -> Self {
+        use GamepadAxisType::*;
+        use GamepadButtonType::*;
+
+        let gamepad_bindings = |button| match button {
+            InputButton::Up => vec![InputBinding::GamepadButton(DPadUp), InputBinding::GamepadAxis { axis: LeftStickY, positive: true }],
+            InputButton::Down => vec![InputBinding::GamepadButton(DPadDown), InputBinding::GamepadAxis { axis: LeftStickY, positive: false }],
+            InputButton::Left => vec![InputBinding::GamepadButton(DPadLeft), InputBinding::GamepadAxis { axis: LeftStickX, positive: false }],
+            InputButton::Right => vec![InputBinding::GamepadButton(DPadRight), InputBinding::GamepadAxis { axis: LeftStickX, positive: true }],
+            InputButton::LightPunch => vec![InputBinding::GamepadButton(West)],
+            InputButton::MediumPunch => vec![InputBinding::GamepadButton(North)],
+            InputButton::HeavyPunch => vec![InputBinding::GamepadButton(RightTrigger)],
+            InputButton::LightKick => vec![InputBinding::GamepadButton(South)],
+            InputButton::MediumKick => vec![InputBinding::GamepadButton(East)],
+            InputButton::HeavyKick => vec![InputBinding::GamepadButton(RightTrigger2)],
+        };
+
+        let player = |gamepad: usize, keys: [KeyCode; 10]| {
+            let buttons = InputButton::ALL.into_iter()
+                .zip(keys)
+                .map(|(button, key)| {
+                    let mut bindings = vec![InputBinding::Key(key)];
+                    bindings.extend(gamepad_bindings(button));
+                    (button, bindings)
+                })
+                .collect();
+            PlayerBindings { gamepad: Some(gamepad), buttons }
+        };
+
+        InputBindings {
+            players: [
+                player(0, [
+                    KeyCode::KeyW, KeyCode::KeyS, KeyCode::KeyA, KeyCode::KeyD,
+                    KeyCode::KeyJ, KeyCode::KeyK, KeyCode::KeyL,
+                    KeyCode::KeyM, KeyCode::Comma, KeyCode::Period,
+                ]),
+                player(1, [
+                    KeyCode::ArrowUp, KeyCode::ArrowDown, KeyCode::ArrowLeft, KeyCode::ArrowRight,
+                    KeyCode::Numpad4, KeyCode::Numpad5, KeyCode::Numpad6,
+                    KeyCode::Numpad1, KeyCode::Numpad2, KeyCode::Numpad3,
+                ]),
+            ],
+        }
+    }
+}
+
+impl InputBindings {
+    pub fn save(&self) -> Result<(), String> {
+        let data = serde_json::to_string_pretty(self).map_err(|error| error.to_string())?;
+        fs::write(INPUT_BINDINGS_PATH, data).map_err(|error| error.to_string())
+    }
+
+    /// Saves the bindings, keeping the ones in use when they can't be written.
+    pub fn save_or_warn(&self) {
+        if let Err(error) = self.save() {
+            warn!("Could not save input bindings to {}: {}", INPUT_BINDINGS_PATH, error);
+        }
+    }
+}
+
+/// Device inputs of both players for the current tick, sampled locally in game and training mode or fed by a replay.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Resource)]
+pub struct PlayerInputs(pub [InputFrame; 2]);
+
+pub(crate) fn sample_player_inputs(
+    game_state: Res<GameState>,
+    bindings: Res<InputBindings>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    gamepad_buttons: Res<ButtonInput<GamepadButton>>,
+    gamepad_axes: Res<Axis<GamepadAxis>>,
+    mut player_inputs: ResMut<PlayerInputs>,
+) {
+    if !matches!(game_state.mode, GameMode::Game | GameMode::Training) {
+        return;
+    }
+
+    for (frame, player_bindings) in player_inputs.0.iter_mut().zip(&bindings.players) {
+        *frame = player_bindings.sample(&keyboard, &gamepad_buttons, &gamepad_axes);
+    }
+}
+
+/// Loads the saved bindings, the defaults when there are none yet or they can't be read.
+fn load_input_bindings(path: &str) -> InputBindings {
+    let Ok(data) = fs::read_to_string(path) else {
+        return InputBindings::default();
+    };
+
+    serde_json::from_str(&data).unwrap_or_else(|error| {
+        warn!("Ignoring unreadable input bindings in {}: {}", path, error);
+        InputBindings::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn history_keeps_the_direction_entries_were_entered_with() {
+        let mut forward = InputFrame::default();
+        forward.set(InputButton::Right, true);
+
+        let mut history = InputHistory::default();
+        history.push(forward, 1);
+        history.push(forward, 1);
+        // Crossing up turns the held direction from forward to back
+        history.push(forward, -1);
+
+        let entries: Vec<(u8, u32)> = history.entries().map(|entry| (entry.numpad, entry.ticks)).collect();
+        assert_eq!(entries, [(4, 1), (6, 2)]);
+        assert_eq!(history.ticks(3).collect::<Vec<_>>(), [forward; 3]);
+    }
+}
@@ -0,0 +1,234 @@
+use bevy::input::InputSystem;
+use bevy_egui::{egui, EguiContexts};
+
+use crate::core::core_core::GameState;
+use crate::core::core_gui::CoreGuiState;
+use crate::game::game_core::Player;
+use crate::game::input::*;
+use crate::game::input::input_core::{InputBinding, InputBindings, InputButton, InputHistory};
+
+/// How far a stick has to be pushed to be picked up as a new binding.
+const REBIND_AXIS_THRESHOLD: f32 = 0.75;
+
+/// History entries listed by the input display.
+const INPUT_DISPLAY_LENGTH: usize = 20;
+
+pub(crate) struct InputGuiPlugin;
+
+impl Plugin for InputGuiPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(RebindState::default())
+            .add_systems(PreUpdate, capture_rebind.after(InputSystem))
+            .add_systems(Update, display_controls)
+            .add_systems(Update, display_input_history);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct RebindTarget {
+    player: usize,
+    button: InputButton,
+    /// Adds the captured input to the bindings instead of replacing them.
+    append: bool,
+}
+
+#[derive(Default, Resource)]
+struct RebindState {
+    listening: Option<RebindTarget>,
+}
+
+/// Binds the next pressed key, gamepad button or stick direction to the button waiting for one.
+/// The captured key is consumed so it doesn't also reach the game or the editor.
+fn capture_rebind(
+    mut rebind_state: ResMut<RebindState>,
+    mut bindings: ResMut<InputBindings>,
+    mut keyboard: ResMut<ButtonInput<KeyCode>>,
+    gamepad_buttons: Res<ButtonInput<GamepadButton>>,
+    gamepad_axes: Res<Axis<GamepadAxis>>,
+    gamepads: Res<Gamepads>,
+) {
+    let Some(target) = rebind_state.listening else { return; };
+
+    if keyboard.just_pressed(KeyCode::Escape) {
+        keyboard.reset(KeyCode::Escape);
+        rebind_state.listening = None;
+        return;
+    }
+
+    let player_gamepad = bindings.players[target.player].gamepad.map(Gamepad::new);
+    let accepts = |gamepad: Gamepad| player_gamepad.is_none_or(|player_gamepad| player_gamepad == gamepad);
+
+    let pressed_key = keyboard.get_just_pressed().next().copied();
+    let captured = if let Some(key) = pressed_key {
+        keyboard.reset(key);
+        Some((InputBinding::Key(key), None))
+    } else if let Some(button) = gamepad_buttons.get_just_pressed().find(|button| accepts(button.gamepad)) {
+        Some((InputBinding::GamepadButton(button.button_type), Some(button.gamepad)))
+    } else {
+        gamepads.iter()
+            .filter(|gamepad| accepts(*gamepad))
+            .flat_map(|gamepad| [GamepadAxisType::LeftStickX, GamepadAxisType::LeftStickY, GamepadAxisType::RightStickX, GamepadAxisType::RightStickY]
+                .map(|axis| (gamepad, axis)))
+            .find_map(|(gamepad, axis)| {
+                let value = gamepad_axes.get(GamepadAxis::new(gamepad, axis)).unwrap_or(0.0);
+                (value.abs() > REBIND_AXIS_THRESHOLD)
+                    .then_some((InputBinding::GamepadAxis { axis, positive: value > 0.0 }, Some(gamepad)))
+            })
+    };
+
+    let Some((binding, gamepad)) = captured else { return; };
+
+    let player_bindings = &mut bindings.players[target.player];
+    if let Some(gamepad) = gamepad {
+        player_bindings.gamepad = Some(gamepad.id);
+    }
+
+    let button_bindings = player_bindings.bindings_mut(target.button);
+    if !target.append {
+        button_bindings.clear();
+    }
+    if !button_bindings.contains(&binding) {
+        button_bindings.push(binding);
+    }
+
+    bindings.save_or_warn();
+    rebind_state.listening = None;
+}
+
+fn display_controls(
+    mut egui_contexts: EguiContexts,
+    mut rebind_state: ResMut<RebindState>,
+    mut bindings: ResMut<InputBindings>,
+    gamepads: Res<Gamepads>,
+) {
+    let ctx = egui_contexts.ctx_mut();
+    let mut changed = false;
+
+    egui::Window::new("Controls").default_open(false).show(ctx, |ui| {
+        egui::Grid::new("input_bindings").num_columns(3).striped(true).show(ui, |ui| {
+            ui.label("");
+            for (player, player_bindings) in bindings.players.iter_mut().enumerate() {
+                let gamepad_label = |gamepad: Option<usize>| gamepad.map_or("Keyboard only".to_string(), |id| format!("Gamepad {}", id));
+
+                egui::ComboBox::from_id_source(("gamepad", player))
+                    .selected_text(format!("P{}: {}", player + 1, gamepad_label(player_bindings.gamepad)))
+                    .show_ui(ui, |ui| {
+                        changed |= ui.selectable_value(&mut player_bindings.gamepad, None, gamepad_label(None)).changed();
+                        for gamepad in gamepads.iter() {
+                            let label = match gamepads.name(gamepad) {
+                                Some(name) => format!("{} ({})", gamepad_label(Some(gamepad.id)), name),
+                                None => gamepad_label(Some(gamepad.id)),
+                            };
+                            changed |= ui.selectable_value(&mut player_bindings.gamepad, Some(gamepad.id), label).changed();
+                        }
+                    });
+            }
+            ui.end_row();
+
+            for button in InputButton::ALL {
+                ui.label(button.label());
+
+                for (player, player_bindings) in bindings.players.iter_mut().enumerate() {
+                    let target = RebindTarget { player, button, append: false };
+                    let listening = rebind_state.listening.is_some_and(|listening| listening.player == player && listening.button == button);
+
+                    ui.horizontal(|ui| {
+                        let text = if listening {
+                            "Press an input, Esc to cancel".to_string()
+                        } else if player_bindings.bindings(button).is_empty() {
+                            "Unbound".to_string()
+                        } else {
+                            player_bindings.bindings(button).iter().map(InputBinding::label).collect::<Vec<_>>().join(", ")
+                        };
+
+                        let response = ui.selectable_label(listening, text)
+                            .on_hover_text("Click to replace, right click to clear");
+                        if response.clicked() {
+                            rebind_state.listening = Some(target);
+                        }
+                        response.context_menu(|ui| {
+                            if ui.button("Clear").clicked() {
+                                player_bindings.bindings_mut(button).clear();
+                                changed = true;
+                                ui.close_menu();
+                            }
+                        });
+
+                        if ui.small_button("+").on_hover_text("Add another input").clicked() {
+                            rebind_state.listening = Some(RebindTarget { append: true, ..target });
+                        }
+                    });
+                }
+                ui.end_row();
+            }
+        });
+
+        if ui.button("Reset to Defaults").clicked() {
+            *bindings = InputBindings::default();
+            rebind_state.listening = None;
+            changed = true;
+        }
+    });
+
+    if changed {
+        bindings.save_or_warn();
+    }
+}
+
+/// Arrow of a numpad direction, a dot for neutral.
+fn direction_glyph(numpad: u8) -> &'static str {
+    match numpad {
+        1 => "↙",
+        2 => "↓",
+        3 => "↘",
+        4 => "←",
+        6 => "→",
+        7 => "↖",
+        8 => "↑",
+        9 => "↗",
+        _ => "•",
+    }
+}
+
+/// Recent inputs of each player, relative to the facing they were entered with, with how many ticks each was held.
+fn display_input_history(
+    mut egui_contexts: EguiContexts,
+    game_state: Res<GameState>,
+    gui_state: Res<CoreGuiState>,
+    query: Query<(&Player, &InputHistory)>,
+) {
+    if !game_state.mode.is_simulated() || !gui_state.show_input_display {
+        return;
+    }
+
+    let ctx = egui_contexts.ctx_mut();
+
+    for (player, history) in &query {
+        let (anchor, offset) = if player.index == 0 {
+            (egui::Align2::LEFT_CENTER, egui::vec2(8.0, 0.0))
+        } else {
+            (egui::Align2::RIGHT_CENTER, egui::vec2(-8.0, 0.0))
+        };
+
+        egui::Area::new(egui::Id::new(("input_display", player.index)))
+            .anchor(anchor, offset)
+            .interactable(false)
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.strong(format!("P{} Inputs", player.index + 1));
+
+                    for entry in history.entries().take(INPUT_DISPLAY_LENGTH) {
+                        let buttons: Vec<&str> = InputButton::ALL.into_iter()
+                            .filter(|button| !button.is_direction() && entry.frame.pressed(*button))
+                            .map(|button| button.label())
+                            .collect();
+
+                        ui.monospace(format!("{:>3} {} {}",
+                            entry.ticks.min(999),
+                            direction_glyph(entry.numpad),
+                            buttons.join(" ")));
+                    }
+                });
+            });
+    }
+}
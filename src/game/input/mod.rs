@@ -0,0 +1,4 @@
+pub mod input_core;
+pub mod input_gui;
+
+use bevy::prelude::*;
@@ -1,4 +1,14 @@
+pub mod character;
+pub mod collision;
 pub mod game_core;
 pub mod game_gui;
+pub mod input;
+pub mod moves;
+pub mod netplay;
+pub mod replay;
+pub mod round;
+pub mod simulation;
+pub mod snapshot;
+pub mod training;
 
 pub use bevy::prelude::*;
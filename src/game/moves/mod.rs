@@ -0,0 +1,3 @@
+pub mod moves_core;
+
+use bevy::prelude::*;
@@ -0,0 +1,408 @@
+use std::fmt;
+use std::fs;
+
+use serde::{Deserialize, Serialize};
+
+use crate::editor::editor_core::EditorClipLoopMode;
+use crate::game::character::character_core::{read_character_input, CharacterInput, StateAnimation};
+use crate::game::collision::collision_core::Facing;
+use crate::game::input::input_core::{InputButton, InputHistory};
+use crate::game::moves::*;
+use crate::game::simulation::simulation_core::{SimulationSchedule, SimulationSet};
+
+pub const MOVE_LIST_PATH: &str = "assets/move_list.json";
+
+pub(crate) struct MovesPlugin;
+
+impl Plugin for MovesPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(load_move_list(MOVE_LIST_PATH))
+            .add_systems(SimulationSchedule, recognize_moves.after(read_character_input).in_set(SimulationSet::Input));
+    }
+}
+
+/// Directional part of a move, written in numpad notation relative to the facing of the character.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum Motion {
+    /// Directions entered in order, like `236` or `623`, other directions may come in between.
+    Sequence(Vec<u8>),
+    /// `[4]6`: `hold` (or its diagonals) held for the charge time, then `release`.
+    Charge { hold: u8, release: u8 },
+    /// `66`: the same direction tapped twice with neutral in between.
+    DoubleTap(u8),
+}
+
+impl Motion {
+    fn last_direction(&self) -> u8 {
+        match self {
+            Motion::Sequence(directions) => directions.last().copied().unwrap_or(5),
+            Motion::Charge { release, .. } => *release,
+            Motion::DoubleTap(direction) => *direction,
+        }
+    }
+}
+
+impl TryFrom<String> for Motion {
+    type Error = String;
+
+    fn try_from(notation: String) -> Result<Self, Self::Error> {
+        let parse_direction = |character: char| character.to_digit(10)
+            .filter(|digit| (1..=9).contains(digit))
+            .map(|digit| digit as u8)
+            .ok_or_else(|| format!("'{}' is not a numpad direction in motion '{}'", character, notation));
+
+        if let Some(charge) = notation.strip_prefix('[') {
+            let mut characters = charge.chars();
+            return match (characters.next(), characters.next(), characters.next(), characters.next()) {
+                (Some(hold), Some(']'), Some(release), None) => Ok(Motion::Charge {
+                    hold: parse_direction(hold)?,
+                    release: parse_direction(release)?,
+                }),
+                _ => Err(format!("charge motion '{}' should look like [4]6", notation)),
+            };
+        }
+
+        let directions = notation.chars().map(parse_direction).collect::<Result<Vec<u8>, String>>()?;
+        match directions.as_slice() {
+            [] => Err("empty motion".to_string()),
+            [first, second] if first == second && *first != 5 => Ok(Motion::DoubleTap(*first)),
+            _ => Ok(Motion::Sequence(directions)),
+        }
+    }
+}
+
+impl From<Motion> for String {
+    fn from(motion: Motion) -> Self {
+        motion.to_string()
+    }
+}
+
+impl fmt::Display for Motion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Motion::Sequence(directions) => directions.iter().try_for_each(|direction| write!(f, "{}", direction)),
+            Motion::Charge { hold, release } => write!(f, "[{}]{}", hold, release),
+            Motion::DoubleTap(direction) => write!(f, "{}{}", direction, direction),
+        }
+    }
+}
+
+/// Timing windows of a motion, in ticks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MotionLeniency {
+    /// Time allowed to enter a whole sequence or double tap.
+    pub motion_window: u32,
+    /// Time allowed between the last direction and the button.
+    pub button_window: u32,
+    /// Time a charge direction has to be held.
+    pub charge_ticks: u32,
+}
+
+impl Default for MotionLeniency {
+    fn default() -> Self {
+        MotionLeniency {
+            motion_window: 20,
+            button_window: 8,
+            charge_ticks: 40,
+        }
+    }
+}
+
+/// A special move performed by a motion, followed by a button when it has one.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MoveDefinition {
+    pub name: String,
+    pub motion: Motion,
+    #[serde(default)]
+    pub button: Option<InputButton>,
+    pub sheet_id: String,
+    /// Named clip of the sheet, the whole sheet when `None` or missing.
+    #[serde(default)]
+    pub clip: Option<String>,
+    #[serde(default)]
+    pub leniency: MotionLeniency,
+}
+
+impl MoveDefinition {
+    pub fn animation(&self) -> StateAnimation {
+        StateAnimation {
+            sheet_id: self.sheet_id.clone(),
+            clip: self.clip.clone(),
+            loop_mode: EditorClipLoopMode::Once,
+        }
+    }
+
+    /// Whether the move is input on the current tick, the newest tick of `history`.
+    pub fn is_input(&self, input: &CharacterInput, history: &InputHistory, facing: Facing) -> bool {
+        let facing_direction = facing.direction();
+        let directions: Vec<u8> = history.ticks(self.leniency.motion_window.max(self.leniency.charge_ticks) as usize * 2)
+            .map(|frame| frame.numpad(facing_direction))
+            .collect();
+
+        // The move comes out on the tick its button, or its last direction when it has none, is pressed
+        let triggered = match self.button {
+            Some(button) => input.just_pressed(button),
+            None => directions.first() == Some(&self.motion.last_direction())
+                && input.previous.numpad(facing_direction) != self.motion.last_direction(),
+        };
+
+        triggered && match &self.motion {
+            Motion::Sequence(sequence) => matches_sequence(&directions, sequence, &self.leniency),
+            Motion::DoubleTap(direction) => matches_sequence(&directions, &[*direction, 5, *direction], &self.leniency),
+            Motion::Charge { hold, release } => matches_charge(&directions, *hold, *release, &self.leniency),
+        }
+    }
+}
+
+/// `directions` go from the current tick back, the sequence is searched backward from its last direction.
+fn matches_sequence(directions: &[u8], sequence: &[u8], leniency: &MotionLeniency) -> bool {
+    let Some((&last, rest)) = sequence.split_last() else { return false; };
+    let Some(last_age) = directions.iter().position(|direction| *direction == last) else { return false; };
+    if last_age > leniency.button_window as usize {
+        return false;
+    }
+
+    let window = &directions[..directions.len().min(leniency.motion_window as usize)];
+    let mut remaining = rest.iter().rev().peekable();
+    for direction in window.iter().skip(last_age + 1) {
+        if remaining.peek() == Some(&direction) {
+            remaining.next();
+        }
+    }
+    remaining.peek().is_none()
+}
+
+/// Directions in the same column or row as `hold`, so `[4]6` also charges from 1 and 7.
+fn charges(direction: u8, hold: u8) -> bool {
+    match hold {
+        4 | 6 => (direction - 1) % 3 == (hold - 1) % 3,
+        2 | 8 => (direction - 1) / 3 == (hold - 1) / 3,
+        _ => direction == hold,
+    }
+}
+
+fn matches_charge(directions: &[u8], hold: u8, release: u8, leniency: &MotionLeniency) -> bool {
+    let Some(release_age) = directions.iter().position(|direction| *direction == release) else { return false; };
+    if release_age > leniency.button_window as usize {
+        return false;
+    }
+
+    let Some(charge_start) = directions.iter()
+        .skip(release_age)
+        .take(leniency.button_window as usize + 1)
+        .position(|direction| charges(*direction, hold)) else { return false; };
+
+    let charged_ticks = directions.iter()
+        .skip(release_age + charge_start)
+        .take_while(|direction| charges(**direction, hold))
+        .count();
+    charged_ticks >= leniency.charge_ticks as usize
+}
+
+/// Special moves of the characters, earlier moves win when several are input on the same tick.
+#[derive(Debug, Default, Clone, Resource, Serialize, Deserialize)]
+pub struct MoveList {
+    pub moves: Vec<MoveDefinition>,
+}
+
+impl MoveList {
+    pub fn recognize(&self, input: &CharacterInput, history: &InputHistory, facing: Facing) -> Option<usize> {
+        self.moves.iter().position(|definition| definition.is_input(input, history, facing))
+    }
+}
+
+fn load_move_list(path: &str) -> MoveList {
+    let data = match fs::read_to_string(path) {
+        Ok(data) => data,
+        Err(error) => {
+            warn!("No move list loaded from {}: {}", path, error);
+            return MoveList::default();
+        }
+    };
+
+    serde_json::from_str(&data).unwrap_or_else(|error| {
+        warn!("Ignoring unreadable move list in {}: {}", path, error);
+        MoveList::default()
+    })
+}
+
+fn recognize_moves(
+    move_list: Res<MoveList>,
+    mut query: Query<(&mut CharacterInput, &InputHistory, &Facing)>,
+) {
+    for (mut input, history, facing) in &mut query {
+        input.special = move_list.recognize(&input, history, *facing);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::input::input_core::InputFrame;
+
+    const PUNCH: Option<InputButton> = Some(InputButton::LightPunch);
+
+    /// Screen space frame of a numpad direction entered facing right.
+    fn frame(direction: u8) -> InputFrame {
+        let mut frame = InputFrame::default();
+        let (horizontal, vertical) = ((direction as i32 - 1) % 3 - 1, (direction as i32 - 1) / 3 - 1);
+        frame.set(InputButton::Left, horizontal < 0);
+        frame.set(InputButton::Right, horizontal > 0);
+        frame.set(InputButton::Down, vertical < 0);
+        frame.set(InputButton::Up, vertical > 0);
+        frame
+    }
+
+    /// Holds each `(direction, ticks)` step in order, then presses `button` for a tick on the last direction.
+    fn is_input(notation: &str, button: Option<InputButton>, steps: &[(u8, u32)], facing: Facing) -> bool {
+        let definition = MoveDefinition {
+            name: notation.to_string(),
+            motion: Motion::try_from(notation.to_string()).unwrap(),
+            button,
+            sheet_id: "test".to_string(),
+            clip: None,
+            leniency: MotionLeniency::default(),
+        };
+
+        let mut frames: Vec<InputFrame> = steps.iter()
+            .flat_map(|(direction, ticks)| std::iter::repeat_n(frame(*direction), *ticks as usize))
+            .collect();
+        if let Some(button) = button {
+            let mut pressed = frames.last().copied().unwrap_or_default();
+            pressed.set(button, true);
+            frames.push(pressed);
+        }
+
+        let mut input = CharacterInput::default();
+        let mut history = InputHistory::default();
+        for frame in frames {
+            let frame = if facing == Facing::Left { frame.mirrored() } else { frame };
+            input.push(frame);
+            history.push(frame, facing.direction());
+        }
+        definition.is_input(&input, &history, facing)
+    }
+
+    /// The first direction of `notation`, `gap` neutral ticks, then the other directions for a tick each.
+    fn spread_motion(notation: &str, gap: u32) -> Vec<(u8, u32)> {
+        let directions: Vec<u8> = notation.chars().map(|character| character.to_digit(10).unwrap() as u8).collect();
+        let mut steps = vec![(directions[0], 1), (5, gap)];
+        steps.extend(directions[1..].iter().map(|direction| (*direction, 1)));
+        steps
+    }
+
+    #[test]
+    fn sequences_are_recognized_within_the_motion_window() {
+        let window = MotionLeniency::default().motion_window;
+        for notation in ["236", "623", "214", "41236"] {
+            // The whole motion, gap and button tick included, fits in the window
+            let widest_gap = window - notation.len() as u32 - 1;
+            assert!(is_input(notation, PUNCH, &spread_motion(notation, widest_gap), Facing::Right), "{}", notation);
+            assert!(!is_input(notation, PUNCH, &spread_motion(notation, widest_gap + 1), Facing::Right), "{}", notation);
+        }
+    }
+
+    #[test]
+    fn sequences_need_the_button_within_the_button_window() {
+        let button_window = MotionLeniency::default().button_window;
+        for notation in ["236", "623", "214", "41236"] {
+            let mut steps = spread_motion(notation, 0);
+            steps.push((5, button_window - 1));
+            assert!(is_input(notation, PUNCH, &steps, Facing::Right), "{}", notation);
+            steps.push((5, 1));
+            assert!(!is_input(notation, PUNCH, &steps, Facing::Right), "{}", notation);
+        }
+    }
+
+    #[test]
+    fn sequences_need_every_direction_in_order() {
+        assert!(!is_input("236", PUNCH, &[(2, 1), (6, 1)], Facing::Right));
+        assert!(!is_input("236", PUNCH, &[(3, 1), (2, 1), (6, 1)], Facing::Right));
+        assert!(!is_input("623", PUNCH, &[(2, 1), (3, 1)], Facing::Right));
+    }
+
+    #[test]
+    fn charges_need_the_full_charge_time() {
+        let charge_ticks = MotionLeniency::default().charge_ticks;
+        assert!(is_input("[4]6", PUNCH, &[(4, charge_ticks), (6, 1)], Facing::Right));
+        assert!(!is_input("[4]6", PUNCH, &[(4, charge_ticks - 1), (6, 1)], Facing::Right));
+        assert!(is_input("[2]8", PUNCH, &[(2, charge_ticks), (8, 1)], Facing::Right));
+        assert!(!is_input("[2]8", PUNCH, &[(2, charge_ticks - 1), (8, 1)], Facing::Right));
+    }
+
+    #[test]
+    fn charges_count_the_diagonals_of_the_hold_direction() {
+        let charge_ticks = MotionLeniency::default().charge_ticks;
+        let half = charge_ticks / 2;
+        assert!(is_input("[4]6", PUNCH, &[(1, half), (7, half), (4, charge_ticks - 2 * half), (6, 1)], Facing::Right));
+        assert!(!is_input("[4]6", PUNCH, &[(1, half), (4, charge_ticks - half - 1), (6, 1)], Facing::Right));
+        assert!(is_input("[2]8", PUNCH, &[(1, half), (3, charge_ticks - half), (8, 1)], Facing::Right));
+        // Neutral breaks the charge
+        assert!(!is_input("[4]6", PUNCH, &[(4, half), (5, 1), (4, charge_ticks - half), (6, 1)], Facing::Right));
+    }
+
+    #[test]
+    fn double_taps_need_neutral_in_between() {
+        assert!(is_input("66", None, &[(6, 2), (5, 2), (6, 1)], Facing::Right));
+        assert!(!is_input("66", None, &[(6, 2), (3, 2), (6, 1)], Facing::Right));
+        assert!(!is_input("66", None, &[(6, 5)], Facing::Right));
+        assert!(!is_input("66", None, &[(6, 2), (5, MotionLeniency::default().motion_window), (6, 1)], Facing::Right));
+    }
+
+    #[test]
+    fn motions_mirror_when_facing_left() {
+        let charge_ticks = MotionLeniency::default().charge_ticks;
+        assert!(is_input("236", PUNCH, &[(2, 1), (3, 1), (6, 1)], Facing::Left));
+        assert!(is_input("623", PUNCH, &[(6, 1), (2, 1), (3, 1)], Facing::Left));
+        assert!(is_input("[4]6", PUNCH, &[(4, charge_ticks), (6, 1)], Facing::Left));
+        assert!(is_input("66", None, &[(6, 2), (5, 2), (6, 1)], Facing::Left));
+
+        // Screen space 236 is 214 for a character facing left
+        let mut screen_space_236 = MoveDefinition {
+            name: "214".to_string(),
+            motion: Motion::Sequence(vec![2, 1, 4]),
+            button: PUNCH,
+            sheet_id: "test".to_string(),
+            clip: None,
+            leniency: MotionLeniency::default(),
+        };
+        let mut input = CharacterInput::default();
+        let mut history = InputHistory::default();
+        let mut punch = frame(6);
+        punch.set(InputButton::LightPunch, true);
+        for frame in [frame(2), frame(3), frame(6), punch] {
+            input.push(frame);
+            history.push(frame, Facing::Left.direction());
+        }
+        assert!(screen_space_236.is_input(&input, &history, Facing::Left));
+        screen_space_236.motion = Motion::Sequence(vec![2, 3, 6]);
+        assert!(!screen_space_236.is_input(&input, &history, Facing::Left));
+        assert!(screen_space_236.is_input(&input, &history, Facing::Right));
+    }
+
+    #[test]
+    fn motion_notation_round_trips() {
+        for (notation, motion) in [
+            ("236", Motion::Sequence(vec![2, 3, 6])),
+            ("41236", Motion::Sequence(vec![4, 1, 2, 3, 6])),
+            ("6", Motion::Sequence(vec![6])),
+            ("55", Motion::Sequence(vec![5, 5])),
+            ("66", Motion::DoubleTap(6)),
+            ("[4]6", Motion::Charge { hold: 4, release: 6 }),
+            ("[2]8", Motion::Charge { hold: 2, release: 8 }),
+        ] {
+            assert_eq!(Motion::try_from(notation.to_string()), Ok(motion.clone()));
+            assert_eq!(motion.to_string(), notation);
+            assert_eq!(serde_json::from_str::<Motion>(&serde_json::to_string(&motion).unwrap()).unwrap(), motion);
+        }
+    }
+
+    #[test]
+    fn bad_motion_notation_is_rejected() {
+        for notation in ["", "0", "203", "23a", "2 3", "[4]", "[4]66", "[46", "[0]6", "[4]x", "4]6"] {
+            assert!(Motion::try_from(notation.to_string()).is_err(), "{}", notation);
+        }
+    }
+}
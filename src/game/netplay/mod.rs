@@ -0,0 +1,4 @@
+pub mod netplay_core;
+pub mod netplay_gui;
+
+use bevy::prelude::*;
@@ -0,0 +1,670 @@
+use std::collections::VecDeque;
+use std::io;
+use std::net::{SocketAddr, UdpSocket};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::core::core_core::{GameMode, GameState};
+use crate::game::game_core::{Controller, VersusSettings};
+use crate::game::input::input_core::{InputBindings, InputFrame, PlayerInputs};
+use crate::game::netplay::*;
+use crate::game::netplay::netplay_gui::NetplayGuiPlugin;
+use crate::game::simulation::simulation_core::{SimulationRng, SimulationSchedule, SimulationTick};
+use crate::game::snapshot::snapshot_core::GameSnapshot;
+
+/// Largest packet read from a socket.
+const MAX_PACKET_SIZE: usize = 4096;
+
+/// Inputs sent at most per packet, the rest follow once these are acknowledged.
+const MAX_INPUTS_PER_PACKET: usize = 128;
+
+/// Local checksums kept around to compare with the ones of the remote peer, which arrive late.
+const CHECKSUM_HISTORY_LENGTH: usize = 120;
+
+/// Both peers seed the simulation RNG with this, they have to draw the same numbers.
+const NETPLAY_SEED: u64 = 0;
+
+pub(crate) struct NetplayPlugin;
+
+impl Plugin for NetplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(NetplayGuiPlugin)
+            .add_systems(FixedUpdate, advance_netplay)
+            .add_systems(Update, end_netplay);
+    }
+}
+
+/// Unreliable, unordered packets to and from the other peer.
+pub trait NetplayTransport: Send + Sync {
+    fn send(&mut self, packet: &[u8]);
+
+    /// Packets received since the last call.
+    fn receive(&mut self) -> Vec<Vec<u8>>;
+}
+
+/// Transport over a non-blocking UDP socket, two instances on one machine talk through localhost.
+pub struct UdpTransport {
+    socket: UdpSocket,
+    peer: SocketAddr,
+}
+
+impl UdpTransport {
+    pub fn bind(local_port: u16, peer: SocketAddr) -> io::Result<Self> {
+        let socket = UdpSocket::bind(("0.0.0.0", local_port))?;
+        socket.set_nonblocking(true)?;
+        Ok(UdpTransport { socket, peer })
+    }
+}
+
+impl NetplayTransport for UdpTransport {
+    fn send(&mut self, packet: &[u8]) {
+        if let Err(error) = self.socket.send_to(packet, self.peer) {
+            debug!("Could not send netplay packet to {}: {}", self.peer, error);
+        }
+    }
+
+    fn receive(&mut self) -> Vec<Vec<u8>> {
+        let mut packets = Vec::new();
+        let mut buffer = [0; MAX_PACKET_SIZE];
+        loop {
+            match self.socket.recv_from(&mut buffer) {
+                Ok((size, from)) if from == self.peer => packets.push(buffer[..size].to_vec()),
+                Ok(_) => {}
+                Err(error) if error.kind() == io::ErrorKind::WouldBlock => break,
+                // Windows reports packets sent before the peer was listening as reset connections
+                Err(error) if error.kind() == io::ErrorKind::ConnectionReset => {}
+                Err(error) => {
+                    debug!("Could not receive netplay packets: {}", error);
+                    break;
+                }
+            }
+        }
+        packets
+    }
+}
+
+/// Network conditions applied by `SimulatedTransport`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NetworkConditions {
+    /// One way delay of every packet.
+    pub latency_ms: u32,
+    /// Up to this much is randomly added to the delay of each packet, reordering some of them.
+    pub jitter_ms: u32,
+    /// Percentage of packets dropped.
+    pub packet_loss: u32,
+}
+
+type PacketQueue = Arc<Mutex<Vec<(Instant, Vec<u8>)>>>;
+
+/// One end of an in-process link, packets reach the other end after the simulated network conditions.
+pub struct SimulatedTransport {
+    outgoing: PacketQueue,
+    incoming: PacketQueue,
+    conditions: NetworkConditions,
+    rng: SimulationRng,
+}
+
+impl SimulatedTransport {
+    pub fn pair(conditions: NetworkConditions, seed: u64) -> (SimulatedTransport, SimulatedTransport) {
+        let first: PacketQueue = Arc::default();
+        let second: PacketQueue = Arc::default();
+        (
+            SimulatedTransport { outgoing: first.clone(), incoming: second.clone(), conditions, rng: SimulationRng::new(seed) },
+            SimulatedTransport { outgoing: second, incoming: first, conditions, rng: SimulationRng::new(!seed) },
+        )
+    }
+}
+
+impl NetplayTransport for SimulatedTransport {
+    fn send(&mut self, packet: &[u8]) {
+        if self.rng.below(100) < self.conditions.packet_loss {
+            return;
+        }
+
+        let delay = self.conditions.latency_ms + self.rng.below(self.conditions.jitter_ms + 1);
+        let Ok(mut outgoing) = self.outgoing.lock() else { return; };
+        outgoing.push((Instant::now() + Duration::from_millis(delay as u64), packet.to_vec()));
+    }
+
+    fn receive(&mut self) -> Vec<Vec<u8>> {
+        let Ok(mut incoming) = self.incoming.lock() else { return Vec::new(); };
+        let now = Instant::now();
+        let (mut arrived, in_flight): (Vec<_>, Vec<_>) = incoming.drain(..).partition(|(arrival, _)| *arrival <= now);
+        *incoming = in_flight;
+
+        arrived.sort_by_key(|(arrival, _)| *arrival);
+        arrived.into_iter().map(|(_, packet)| packet).collect()
+    }
+}
+
+/// Sent every tick until the peer answers with its own, both peers have to play the same match with the same data.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NetplayHandshake {
+    /// Player index played by the sender.
+    pub local_player: usize,
+    pub versus_settings: VersusSettings,
+    /// `game_data_hash` of the sheets, animations and moves of the sender.
+    pub game_data_hash: u64,
+}
+
+impl NetplayHandshake {
+    pub fn new(local_player: usize, versus_settings: &VersusSettings, game_data_hash: u64) -> Self {
+        // Both characters are played by humans in netplay whatever their controller
+        let mut versus_settings = versus_settings.clone();
+        for player in &mut versus_settings.players {
+            player.controller = Controller::Human;
+        }
+
+        NetplayHandshake { local_player, versus_settings, game_data_hash }
+    }
+
+    /// Why a match can't be played against the sender of `remote`, `None` when it can.
+    fn mismatch(&self, remote: &NetplayHandshake) -> Option<String> {
+        if remote.local_player == self.local_player {
+            Some(format!("Both peers play P{}", self.local_player + 1))
+        } else if remote.versus_settings != self.versus_settings {
+            Some("Versus settings differ between the peers".to_string())
+        } else if remote.game_data_hash != self.game_data_hash {
+            Some("Sheet data, animations or moves differ between the peers".to_string())
+        } else {
+            None
+        }
+    }
+}
+
+/// Sent every tick, repeating the inputs the receiver hasn't acknowledged so lost packets don't matter.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct NetplayPacket {
+    /// Tick the first of `inputs` applies to.
+    start_tick: u64,
+    inputs: Vec<InputFrame>,
+    /// Number of the receiver's inputs the sender has.
+    received: u64,
+    /// Tick and checksum of the latest state the sender simulated from confirmed inputs only.
+    checksum: Option<(u64, u64)>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+enum NetplayMessage {
+    Handshake(NetplayHandshake),
+    Inputs(NetplayPacket),
+}
+
+/// Whether the peers agreed on the match, nothing is simulated before they do.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NetplayPeer {
+    Connecting,
+    Connected,
+    /// The handshake of the peer didn't match the local one.
+    Refused(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RollbackSettings {
+    /// Player index played on this side.
+    pub local_player: usize,
+    /// Ticks between sampling a local input and simulating it, hides that much latency without rolling back.
+    pub input_delay: u32,
+    /// Ticks simulated at most past the last received remote input, the session waits for the peer beyond that.
+    pub max_rollback: u32,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct NetplayStats {
+    pub tick: u64,
+    /// Tick up to which the remote inputs are known.
+    pub confirmed_tick: u64,
+    pub rollbacks: u64,
+    pub resimulated_ticks: u64,
+    /// Fixed updates spent waiting for the remote peer.
+    pub stalls: u64,
+    /// First tick whose checksum differed between the peers.
+    pub desync_tick: Option<u64>,
+}
+
+/// GGPO style rollback: remote inputs are predicted by repeating the last received one,
+/// and the ticks simulated with a wrong prediction are simulated again once the real input arrives.
+pub struct RollbackSession {
+    pub settings: RollbackSettings,
+    transport: Box<dyn NetplayTransport>,
+    handshake: NetplayHandshake,
+    pub peer: NetplayPeer,
+    /// Inputs of both players from tick 1 on, local ones are known `input_delay` ticks ahead.
+    inputs: [Vec<InputFrame>; 2],
+    /// Inputs every tick was simulated with, from tick 1 on.
+    simulated_inputs: Vec<[InputFrame; 2]>,
+    /// States before each of the last simulated ticks, oldest first.
+    snapshots: VecDeque<GameSnapshot>,
+    /// Number of local inputs the remote peer has.
+    remote_received: u64,
+    /// Tick up to which the simulated remote inputs are known to be right.
+    verified_tick: u64,
+    local_checksums: VecDeque<(u64, u64)>,
+    remote_checksums: Vec<(u64, u64)>,
+    pub stats: NetplayStats,
+}
+
+impl RollbackSession {
+    pub fn new(settings: RollbackSettings, transport: Box<dyn NetplayTransport>, handshake: NetplayHandshake) -> Self {
+        let mut inputs = [Vec::new(), Vec::new()];
+        inputs[settings.local_player] = vec![InputFrame::default(); settings.input_delay as usize];
+
+        RollbackSession {
+            settings,
+            transport,
+            handshake,
+            peer: NetplayPeer::Connecting,
+            inputs,
+            simulated_inputs: Vec::new(),
+            snapshots: VecDeque::new(),
+            remote_received: 0,
+            verified_tick: 0,
+            local_checksums: VecDeque::new(),
+            remote_checksums: Vec::new(),
+            stats: NetplayStats::default(),
+        }
+    }
+
+    fn remote_player(&self) -> usize {
+        1 - self.settings.local_player
+    }
+
+    /// Ticks the remote inputs are known for.
+    fn confirmed_tick(&self) -> u64 {
+        self.inputs[self.remote_player()].len() as u64
+    }
+
+    /// Exchanges inputs with the peer, rolls back mispredicted ticks and simulates the next one,
+    /// unless that would go further than `max_rollback` past the remote inputs.
+    pub fn advance(&mut self, world: &mut World, local_input: InputFrame) {
+        self.receive();
+        match self.peer {
+            NetplayPeer::Connecting => return self.send_handshake(),
+            NetplayPeer::Refused(_) => return,
+            NetplayPeer::Connected => {}
+        }
+
+        self.roll_back(world);
+
+        let next_tick = world.resource::<SimulationTick>().0 + 1;
+        if next_tick > self.confirmed_tick() + self.settings.max_rollback as u64 {
+            self.stats.stalls += 1;
+        } else {
+            self.inputs[self.settings.local_player].push(local_input);
+            self.simulate_tick(world);
+        }
+
+        self.check_desync();
+        self.stats.tick = world.resource::<SimulationTick>().0;
+        self.stats.confirmed_tick = self.confirmed_tick();
+        self.send_inputs();
+    }
+
+    /// Inputs of `tick`, the last received remote input is repeated until the real one arrives.
+    fn inputs_for(&self, tick: u64) -> [InputFrame; 2] {
+        let index = (tick - 1) as usize;
+        let mut frames = [InputFrame::default(); 2];
+        for (frame, inputs) in frames.iter_mut().zip(&self.inputs) {
+            *frame = inputs.get(index).or(inputs.last()).copied().unwrap_or_default();
+        }
+        frames
+    }
+
+    /// Saves the current state, then simulates the tick after it.
+    fn simulate_tick(&mut self, world: &mut World) {
+        let snapshot = GameSnapshot::save(world);
+        let tick = snapshot.tick() + 1;
+        self.record_checksum(&snapshot);
+
+        self.snapshots.push_back(snapshot);
+        while self.snapshots.len() > self.settings.max_rollback as usize + 1 {
+            self.snapshots.pop_front();
+        }
+
+        let inputs = self.inputs_for(tick);
+        self.simulated_inputs.truncate((tick - 1) as usize);
+        self.simulated_inputs.push(inputs);
+
+        world.resource_mut::<PlayerInputs>().0 = inputs;
+        world.run_schedule(SimulationSchedule);
+    }
+
+    /// Restores the state before the first tick simulated with a wrong remote input and simulates up to the current tick again.
+    fn roll_back(&mut self, world: &mut World) {
+        let remote = self.remote_player();
+        let current_tick = world.resource::<SimulationTick>().0;
+        let checked_tick = current_tick.min(self.confirmed_tick());
+
+        let mispredicted = (self.verified_tick + 1..=checked_tick)
+            .find(|tick| self.simulated_inputs[(tick - 1) as usize][remote] != self.inputs[remote][(tick - 1) as usize]);
+        self.verified_tick = self.verified_tick.max(checked_tick);
+
+        let Some(first_tick) = mispredicted else { return; };
+        let Some(index) = self.snapshots.iter().position(|snapshot| snapshot.tick() == first_tick - 1) else {
+            warn!("No snapshot left to roll back to tick {}", first_tick);
+            return;
+        };
+
+        self.snapshots[index].restore(world);
+        self.snapshots.truncate(index);
+        for _ in first_tick..=current_tick {
+            self.simulate_tick(world);
+        }
+
+        self.stats.rollbacks += 1;
+        self.stats.resimulated_ticks += current_tick - first_tick + 1;
+    }
+
+    /// Keeps the checksum of a state the remote peer computes as well, once every input leading to it is confirmed.
+    fn record_checksum(&mut self, snapshot: &GameSnapshot) {
+        let tick = snapshot.tick();
+        let recorded = self.local_checksums.back().is_some_and(|(last_tick, _)| *last_tick >= tick);
+        if tick == 0 || tick > self.confirmed_tick() || recorded {
+            return;
+        }
+
+        self.local_checksums.push_back((tick, snapshot.checksum()));
+        while self.local_checksums.len() > CHECKSUM_HISTORY_LENGTH {
+            self.local_checksums.pop_front();
+        }
+    }
+
+    fn check_desync(&mut self) {
+        let local_checksums = &self.local_checksums;
+        let newest_tick = local_checksums.back().map_or(0, |(tick, _)| *tick);
+        let mut desync_tick = self.stats.desync_tick;
+
+        self.remote_checksums.retain(|(tick, checksum)| {
+            match local_checksums.iter().find(|(local_tick, _)| local_tick == tick) {
+                Some((_, local_checksum)) => {
+                    if local_checksum != checksum && desync_tick.is_none() {
+                        error!("Netplay desync at tick {}: local checksum {:016x}, remote {:016x}", tick, local_checksum, checksum);
+                        desync_tick = Some(*tick);
+                    }
+                    false
+                }
+                // Not computed locally yet
+                None => *tick > newest_tick,
+            }
+        });
+
+        self.stats.desync_tick = desync_tick;
+    }
+
+    fn receive(&mut self) {
+        for bytes in self.transport.receive() {
+            match serde_json::from_slice::<NetplayMessage>(&bytes) {
+                Ok(NetplayMessage::Handshake(remote)) => self.receive_handshake(&remote),
+                Ok(NetplayMessage::Inputs(packet)) if self.peer == NetplayPeer::Connected => self.receive_inputs(packet),
+                _ => {}
+            }
+        }
+    }
+
+    /// A peer still connecting sends its handshake every tick until ours arrives, so it's answered once decided.
+    fn receive_handshake(&mut self, remote: &NetplayHandshake) {
+        if self.peer != NetplayPeer::Connecting {
+            return self.send_handshake();
+        }
+
+        self.peer = match self.handshake.mismatch(remote) {
+            Some(reason) => {
+                error!("Netplay peer refused: {}", reason);
+                NetplayPeer::Refused(reason)
+            }
+            None => NetplayPeer::Connected,
+        };
+    }
+
+    fn receive_inputs(&mut self, packet: NetplayPacket) {
+        let remote = self.remote_player();
+        self.remote_received = self.remote_received.max(packet.received);
+
+        // Packets start at the first input the sender thinks is missing, the ones before are known already
+        let known = self.inputs[remote].len() as u64;
+        if packet.start_tick <= known + 1 {
+            let skip = (known + 1 - packet.start_tick) as usize;
+            self.inputs[remote].extend(packet.inputs.iter().skip(skip));
+        }
+
+        if let Some(checksum) = packet.checksum {
+            self.remote_checksums.push(checksum);
+        }
+    }
+
+    fn send_handshake(&mut self) {
+        self.send(&NetplayMessage::Handshake(self.handshake.clone()));
+    }
+
+    fn send_inputs(&mut self) {
+        let local_inputs = &self.inputs[self.settings.local_player];
+        let start = (self.remote_received as usize).min(local_inputs.len());
+        let end = local_inputs.len().min(start + MAX_INPUTS_PER_PACKET);
+
+        let packet = NetplayPacket {
+            start_tick: start as u64 + 1,
+            inputs: local_inputs[start..end].to_vec(),
+            received: self.confirmed_tick(),
+            checksum: self.local_checksums.back().copied(),
+        };
+        self.send(&NetplayMessage::Inputs(packet));
+    }
+
+    fn send(&mut self, message: &NetplayMessage) {
+        match serde_json::to_vec(message) {
+            Ok(bytes) => self.transport.send(&bytes),
+            Err(error) => warn!("Could not encode netplay packet: {}", error),
+        }
+    }
+}
+
+/// Second session simulated in the same world to test netplay on one machine.
+/// Its state is swapped in only while it advances, so only the local side is ever rendered.
+pub struct LoopbackPeer {
+    pub session: RollbackSession,
+    state: Option<GameSnapshot>,
+}
+
+impl LoopbackPeer {
+    pub fn new(session: RollbackSession) -> Self {
+        LoopbackPeer { session, state: None }
+    }
+}
+
+/// Netplay match in progress, removed when netplay mode stops.
+#[derive(Resource)]
+pub struct Netplay {
+    pub session: RollbackSession,
+    pub loopback_peer: Option<LoopbackPeer>,
+    started: bool,
+}
+
+impl Netplay {
+    pub fn new(session: RollbackSession, loopback_peer: Option<LoopbackPeer>) -> Self {
+        Netplay { session, loopback_peer, started: false }
+    }
+}
+
+/// Reads the devices bound to `player`, each side of a loopback match plays with its own bindings.
+fn sample_local_input(world: &World, player: usize) -> InputFrame {
+    let Some(bindings) = world.resource::<InputBindings>().players.get(player) else {
+        return InputFrame::default();
+    };
+
+    bindings.sample(
+        world.resource::<ButtonInput<KeyCode>>(),
+        world.resource::<ButtonInput<GamepadButton>>(),
+        world.resource::<Axis<GamepadAxis>>(),
+    )
+}
+
+fn advance_netplay(world: &mut World) {
+    if world.resource::<GameState>().mode != GameMode::Netplay {
+        return;
+    }
+
+    let Some(mut netplay) = world.remove_resource::<Netplay>() else { return; };
+
+    if !netplay.started {
+        netplay.started = true;
+        world.insert_resource(SimulationRng::new(NETPLAY_SEED));
+        if let Some(peer) = netplay.loopback_peer.as_mut() {
+            peer.state = Some(GameSnapshot::save(world));
+        }
+    }
+
+    let local_input = sample_local_input(world, netplay.session.settings.local_player);
+    netplay.session.advance(world, local_input);
+
+    if let Some(peer) = netplay.loopback_peer.as_mut() {
+        let local_state = GameSnapshot::save(world);
+        if let Some(state) = &peer.state {
+            state.restore(world);
+        }
+
+        let peer_input = sample_local_input(world, peer.session.settings.local_player);
+        peer.session.advance(world, peer_input);
+        peer.state = Some(GameSnapshot::save(world));
+        local_state.restore(world);
+    }
+
+    world.insert_resource(netplay);
+}
+
+/// Closes the connection once netplay mode stops.
+fn end_netplay(
+    mut commands: Commands,
+    game_state: Res<GameState>,
+    netplay: Option<Res<Netplay>>,
+    mut was_playing: Local<bool>,
+) {
+    let playing = game_state.mode == GameMode::Netplay;
+    if *was_playing && !playing && netplay.is_some() {
+        commands.remove_resource::<Netplay>();
+    }
+    *was_playing = playing;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::game_core::test_match_world;
+    use crate::game::input::input_core::InputButton;
+
+    fn session_pair(handshakes: [NetplayHandshake; 2]) -> [RollbackSession; 2] {
+        let conditions = NetworkConditions { latency_ms: 0, jitter_ms: 0, packet_loss: 0 };
+        let (first, second) = SimulatedTransport::pair(conditions, 0);
+        let [first_handshake, second_handshake] = handshakes;
+        let settings = |local_player| RollbackSettings { local_player, input_delay: 0, max_rollback: 8 };
+        [
+            RollbackSession::new(settings(0), Box::new(first), first_handshake),
+            RollbackSession::new(settings(1), Box::new(second), second_handshake),
+        ]
+    }
+
+    /// The first session sends its handshake, then the second one, like two peers connecting a tick apart.
+    fn connect(sessions: &mut [RollbackSession; 2]) {
+        let [first, second] = sessions;
+        first.send_handshake();
+        second.receive();
+        first.send_handshake();
+        second.receive();
+        first.receive();
+    }
+
+    #[test]
+    fn matching_handshakes_connect_both_peers() {
+        let versus_settings = VersusSettings::default();
+        // Controllers don't matter, both players are human in netplay
+        let mut other_settings = versus_settings.clone();
+        other_settings.players[1].controller = Controller::Human;
+        let mut sessions = session_pair([
+            NetplayHandshake::new(0, &versus_settings, 1),
+            NetplayHandshake::new(1, &other_settings, 1),
+        ]);
+
+        connect(&mut sessions);
+        assert_eq!(sessions[0].peer, NetplayPeer::Connected);
+        assert_eq!(sessions[1].peer, NetplayPeer::Connected);
+    }
+
+    #[test]
+    fn mismatching_handshakes_refuse_both_peers() {
+        let versus_settings = VersusSettings::default();
+        let mut other_settings = versus_settings.clone();
+        other_settings.rounds_to_win += 1;
+
+        for handshakes in [
+            [NetplayHandshake::new(0, &versus_settings, 1), NetplayHandshake::new(1, &versus_settings, 2)],
+            [NetplayHandshake::new(0, &versus_settings, 1), NetplayHandshake::new(1, &other_settings, 1)],
+            [NetplayHandshake::new(0, &versus_settings, 1), NetplayHandshake::new(0, &versus_settings, 1)],
+        ] {
+            let mut sessions = session_pair(handshakes);
+            connect(&mut sessions);
+            assert!(matches!(sessions[0].peer, NetplayPeer::Refused(_)));
+            assert!(matches!(sessions[1].peer, NetplayPeer::Refused(_)));
+        }
+    }
+
+    /// Input of `player` on its `count`th sampled tick: walking back and forth and punching now and then,
+    /// different for both players and changing often enough for predictions to miss.
+    fn scripted_input(player: usize, count: u64) -> InputFrame {
+        let mut frame = InputFrame::default();
+        let period = 23 + player as u64 * 14;
+        let walk = if (count / period).is_multiple_of(2) { InputButton::Right } else { InputButton::Left };
+        frame.set(walk, true);
+        frame.set(InputButton::LightPunch, count.is_multiple_of(11 + player as u64 * 6));
+        frame
+    }
+
+    #[test]
+    fn sessions_over_a_lossy_link_roll_back_and_stay_in_sync() {
+        let conditions = NetworkConditions { latency_ms: 12, jitter_ms: 8, packet_loss: 15 };
+        let (first, second) = SimulatedTransport::pair(conditions, 3);
+        let transports: [Box<dyn NetplayTransport>; 2] = [Box::new(first), Box::new(second)];
+        let versus_settings = VersusSettings::default();
+
+        let mut peers: Vec<(RollbackSession, World)> = transports.into_iter().enumerate()
+            .map(|(local_player, transport)| {
+                let settings = RollbackSettings { local_player, input_delay: 2, max_rollback: 8 };
+                let handshake = NetplayHandshake::new(local_player, &versus_settings, 0);
+                let mut world = test_match_world(GameMode::Netplay, Controller::Human);
+                world.insert_resource(SimulationRng::new(NETPLAY_SEED));
+                (RollbackSession::new(settings, transport, handshake), world)
+            })
+            .collect();
+
+        const TICKS: u64 = 300;
+        let mut samples = [0; 2];
+        for _ in 0..20_000 {
+            if peers.iter().all(|(session, _)| session.confirmed_tick() >= TICKS && session.stats.tick >= TICKS) {
+                break;
+            }
+
+            for (player, (session, world)) in peers.iter_mut().enumerate() {
+                samples[player] += 1;
+                session.advance(world, scripted_input(player, samples[player]));
+            }
+            std::thread::sleep(Duration::from_millis(1));
+        }
+
+        for (session, _) in &peers {
+            assert!(session.stats.tick >= TICKS, "stuck at tick {}", session.stats.tick);
+            assert!(session.stats.rollbacks > 0, "mispredicted inputs were rolled back");
+            assert!(session.stats.resimulated_ticks >= session.stats.rollbacks);
+            assert_eq!(session.stats.desync_tick, None);
+        }
+
+        // Local checksums are the `GameSnapshot::checksum` of ticks simulated from confirmed inputs only
+        let [(first, _), (second, _)] = peers.as_slice() else { unreachable!() };
+        let mut compared_ticks = 0;
+        for (tick, checksum) in &first.local_checksums {
+            let Some((_, other_checksum)) = second.local_checksums.iter().find(|(other_tick, _)| other_tick == tick) else { continue; };
+            assert_eq!(checksum, other_checksum, "checksums differ at tick {}", tick);
+            compared_ticks += 1;
+        }
+        assert!(compared_ticks > 0);
+    }
+}
@@ -0,0 +1,223 @@
+use std::net::SocketAddr;
+
+use bevy_egui::{egui, EguiContexts};
+
+use crate::core::core_core::{apply_requested_mode, GameMode, GameState, ModeRequestSet};
+use crate::editor::editor_core::EditorSpriteSheets;
+use crate::game::character::character_core::CharacterAnimations;
+use crate::game::game_core::VersusSettings;
+use crate::game::moves::moves_core::MoveList;
+use crate::game::netplay::*;
+use crate::game::netplay::netplay_core::{
+    LoopbackPeer, Netplay, NetplayHandshake, NetplayPeer, NetworkConditions, RollbackSession, RollbackSettings,
+    SimulatedTransport, UdpTransport,
+};
+use crate::game::replay::replay_core::game_data_hash;
+
+pub(crate) struct NetplayGuiPlugin;
+
+impl Plugin for NetplayGuiPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(NetplaySetup::default())
+            .add_systems(Update, display_netplay_setup.in_set(ModeRequestSet))
+            .add_systems(Update, start_netplay.after(apply_requested_mode))
+            .add_systems(Update, display_netplay_stats.in_set(ModeRequestSet));
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum NetplayConnection {
+    /// Both players in this instance, linked by a simulated network.
+    Loopback,
+    Udp,
+}
+
+/// Options of the next netplay match, edited in the editor.
+#[derive(Resource)]
+struct NetplaySetup {
+    connection: NetplayConnection,
+    local_player: usize,
+    local_port: u16,
+    peer_address: String,
+    input_delay: u32,
+    max_rollback: u32,
+    conditions: NetworkConditions,
+    error: Option<String>,
+}
+
+impl Default for NetplaySetup {
+    fn default() -> Self {
+        NetplaySetup {
+            connection: NetplayConnection::Loopback,
+            local_player: 0,
+            local_port: 7000,
+            peer_address: "127.0.0.1:7001".to_string(),
+            input_delay: 2,
+            max_rollback: 8,
+            conditions: NetworkConditions { latency_ms: 60, jitter_ms: 20, packet_loss: 5 },
+            error: None,
+        }
+    }
+}
+
+impl NetplaySetup {
+    fn start(&self, versus_settings: &VersusSettings, game_data_hash: u64) -> Result<Netplay, String> {
+        let settings = RollbackSettings {
+            local_player: self.local_player,
+            input_delay: self.input_delay,
+            max_rollback: self.max_rollback,
+        };
+        let handshake = |local_player| NetplayHandshake::new(local_player, versus_settings, game_data_hash);
+
+        match self.connection {
+            NetplayConnection::Loopback => {
+                let (local, remote) = SimulatedTransport::pair(self.conditions, 0);
+                let peer_settings = RollbackSettings { local_player: 1 - self.local_player, ..settings };
+                Ok(Netplay::new(
+                    RollbackSession::new(settings, Box::new(local), handshake(settings.local_player)),
+                    Some(LoopbackPeer::new(RollbackSession::new(
+                        peer_settings,
+                        Box::new(remote),
+                        handshake(peer_settings.local_player),
+                    ))),
+                ))
+            }
+            NetplayConnection::Udp => {
+                let peer: SocketAddr = self.peer_address.trim().parse()
+                    .map_err(|error| format!("Invalid peer address {}: {}", self.peer_address, error))?;
+                let transport = UdpTransport::bind(self.local_port, peer)
+                    .map_err(|error| format!("Could not bind port {}: {}", self.local_port, error))?;
+                Ok(Netplay::new(RollbackSession::new(settings, Box::new(transport), handshake(settings.local_player)), None))
+            }
+        }
+    }
+}
+
+fn display_netplay_setup(
+    mut egui_contexts: EguiContexts,
+    mut game_state: ResMut<GameState>,
+    mut setup: ResMut<NetplaySetup>,
+) {
+    if game_state.mode != GameMode::Editor {
+        return;
+    }
+
+    egui::Window::new("Netplay").default_open(false).show(egui_contexts.ctx_mut(), |ui| {
+        ui.horizontal(|ui| {
+            ui.radio_value(&mut setup.connection, NetplayConnection::Loopback, "Loopback");
+            ui.radio_value(&mut setup.connection, NetplayConnection::Udp, "UDP");
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("Local Player");
+            ui.radio_value(&mut setup.local_player, 0, "P1");
+            ui.radio_value(&mut setup.local_player, 1, "P2");
+        });
+
+        match setup.connection {
+            NetplayConnection::Loopback => {
+                ui.label("The other player is simulated in this instance with their own bindings.");
+                ui.add(egui::Slider::new(&mut setup.conditions.latency_ms, 0..=300).text("Latency (ms)"));
+                ui.add(egui::Slider::new(&mut setup.conditions.jitter_ms, 0..=100).text("Jitter (ms)"));
+                ui.add(egui::Slider::new(&mut setup.conditions.packet_loss, 0..=50).text("Packet Loss (%)"));
+            }
+            NetplayConnection::Udp => {
+                ui.horizontal(|ui| {
+                    ui.label("Local Port");
+                    ui.add(egui::DragValue::new(&mut setup.local_port));
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Peer Address");
+                    ui.text_edit_singleline(&mut setup.peer_address);
+                });
+            }
+        }
+
+        ui.add(egui::Slider::new(&mut setup.input_delay, 0..=8).text("Input Delay"));
+        ui.add(egui::Slider::new(&mut setup.max_rollback, 1..=15).text("Max Rollback"));
+
+        if let Some(error) = &setup.error {
+            ui.colored_label(egui::Color32::LIGHT_RED, error);
+        }
+
+        if ui.button("Start").clicked() {
+            game_state.requested_mode = Some(GameMode::Netplay);
+            setup.error = None;
+        }
+    });
+}
+
+/// Connects once netplay mode is entered, so a switch held back by the unsaved changes prompt binds no socket.
+/// Goes back to the editor with the error when the connection can't be set up.
+#[allow(clippy::too_many_arguments)]
+fn start_netplay(
+    mut commands: Commands,
+    mut game_state: ResMut<GameState>,
+    mut setup: ResMut<NetplaySetup>,
+    netplay: Option<Res<Netplay>>,
+    versus_settings: Res<VersusSettings>,
+    sprite_sheets: Res<EditorSpriteSheets>,
+    character_animations: Res<CharacterAnimations>,
+    move_list: Res<MoveList>,
+) {
+    if game_state.mode != GameMode::Netplay || netplay.is_some() {
+        return;
+    }
+
+    let hash = game_data_hash(&sprite_sheets, &character_animations, &move_list);
+    match setup.start(&versus_settings, hash) {
+        Ok(netplay) => commands.insert_resource(netplay),
+        Err(error) => {
+            setup.error = Some(error);
+            game_state.requested_mode = Some(GameMode::Editor);
+        }
+    }
+}
+
+fn display_netplay_stats(
+    mut egui_contexts: EguiContexts,
+    mut game_state: ResMut<GameState>,
+    netplay: Option<Res<Netplay>>,
+) {
+    if game_state.mode != GameMode::Netplay {
+        return;
+    }
+
+    let Some(netplay) = netplay else { return; };
+
+    egui::Window::new("Netplay").show(egui_contexts.ctx_mut(), |ui| {
+        display_session_stats(ui, "Local", &netplay.session);
+        if let Some(peer) = &netplay.loopback_peer {
+            ui.separator();
+            display_session_stats(ui, "Loopback Peer", &peer.session);
+        }
+
+        if ui.button("Stop").clicked() {
+            game_state.requested_mode = Some(GameMode::Editor);
+        }
+    });
+}
+
+fn display_session_stats(ui: &mut egui::Ui, name: &str, session: &RollbackSession) {
+    ui.strong(name);
+    match &session.peer {
+        NetplayPeer::Connecting => {
+            ui.label("Waiting for the peer...");
+            return;
+        }
+        NetplayPeer::Refused(reason) => {
+            ui.colored_label(egui::Color32::LIGHT_RED, format!("Refused: {}", reason));
+            return;
+        }
+        NetplayPeer::Connected => {}
+    }
+
+    let stats = &session.stats;
+    ui.label(format!("Tick: {} (confirmed {})", stats.tick, stats.confirmed_tick));
+    ui.label(format!("Rollbacks: {} ({} ticks resimulated)", stats.rollbacks, stats.resimulated_ticks));
+    ui.label(format!("Stalls: {}", stats.stalls));
+    match stats.desync_tick {
+        Some(tick) => ui.colored_label(egui::Color32::LIGHT_RED, format!("Desync at tick {}", tick)),
+        None => ui.label("In sync"),
+    };
+}
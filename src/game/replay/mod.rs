@@ -0,0 +1,4 @@
+pub mod replay_core;
+pub mod replay_gui;
+
+use bevy::prelude::*;
@@ -0,0 +1,428 @@
+use std::fs;
+use std::hash::Hasher;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bevy::app::AppExit;
+use bevy::ecs::system::RunSystemOnce;
+use serde::{Deserialize, Serialize};
+
+use crate::core::core_core::{apply_requested_mode, GameMode, GameState};
+use crate::editor::editor_core::EditorSpriteSheets;
+use crate::game::character::character_core::{read_character_input, CharacterAnimations, CharacterState};
+use crate::game::game_core::{restart_match, VersusSettings};
+use crate::game::input::input_core::{sample_player_inputs, InputFrame, PlayerInputs};
+use crate::game::moves::moves_core::MoveList;
+use crate::game::replay::*;
+use crate::game::replay::replay_gui::ReplayGuiPlugin;
+use crate::game::round::round_core::RoundState;
+use crate::game::snapshot::snapshot_core::{FnvHasher, GameSnapshot};
+use crate::game::simulation::simulation_core::{SimulationRng, SimulationSchedule, SimulationSet, SimulationTick};
+
+pub const REPLAYS_DIRECTORY: &str = "replays";
+
+/// Bumped whenever the replay format or the simulation changes in a way old replays can't follow.
+const REPLAY_VERSION: u32 = 2;
+
+/// Ticks between the snapshots seeking starts from, instead of playing the match again from its start.
+const REPLAY_CHECKPOINT_INTERVAL: u64 = 300;
+
+pub(crate) struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(ReplayGuiPlugin)
+            .insert_resource(ReplayRecorder::default())
+            .add_systems(Update, begin_match.after(apply_requested_mode))
+            .add_systems(Update, control_replay.after(begin_match))
+            .add_systems(Last, save_recording_on_exit)
+            .add_systems(SimulationSchedule, (
+                record_inputs.after(sample_player_inputs),
+                feed_replay_inputs,
+            ).before(read_character_input).in_set(SimulationSet::Input))
+            .add_systems(SimulationSchedule, save_replay_checkpoint.after(SimulationSet::Reaction));
+    }
+}
+
+/// Everything needed to start the recorded match over.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplayHeader {
+    pub version: u32,
+    pub versus_settings: VersusSettings,
+    /// `game_data_hash` of the data the match was played with.
+    pub game_data_hash: u64,
+    pub seed: u64,
+}
+
+/// Inputs of both players held for `ticks` consecutive ticks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReplayInputRun {
+    pub ticks: u32,
+    pub inputs: [InputFrame; 2],
+}
+
+/// A recorded match, inputs are run-length encoded since they rarely change from one tick to the next.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Replay {
+    pub header: ReplayHeader,
+    pub inputs: Vec<ReplayInputRun>,
+    /// Ticks on which a rematch was requested.
+    pub rematch_ticks: Vec<u64>,
+}
+
+impl Replay {
+    pub fn new(header: ReplayHeader) -> Self {
+        Replay { header, inputs: Vec::new(), rematch_ticks: Vec::new() }
+    }
+
+    /// Appends the inputs of the next tick.
+    pub fn push(&mut self, inputs: [InputFrame; 2]) {
+        match self.inputs.last_mut() {
+            Some(run) if run.inputs == inputs && run.ticks < u32::MAX => run.ticks += 1,
+            _ => self.inputs.push(ReplayInputRun { ticks: 1, inputs }),
+        }
+    }
+
+    pub fn ticks(&self) -> u64 {
+        self.inputs.iter().map(|run| run.ticks as u64).sum()
+    }
+
+    /// Inputs of every tick, the first tick at index 0.
+    pub fn decode(&self) -> Vec<[InputFrame; 2]> {
+        self.inputs.iter()
+            .flat_map(|run| std::iter::repeat_n(run.inputs, run.ticks as usize))
+            .collect()
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        if let Some(directory) = path.parent() {
+            fs::create_dir_all(directory).map_err(|error| error.to_string())?;
+        }
+
+        let data = serde_json::to_string(self).map_err(|error| error.to_string())?;
+        fs::write(path, data).map_err(|error| error.to_string())
+    }
+
+    pub fn load(path: &Path) -> Result<Replay, String> {
+        let data = fs::read_to_string(path).map_err(|error| error.to_string())?;
+        let replay: Replay = serde_json::from_str(&data).map_err(|error| error.to_string())?;
+        if replay.header.version != REPLAY_VERSION {
+            return Err(format!("Unsupported replay version {}", replay.header.version));
+        }
+
+        Ok(replay)
+    }
+}
+
+/// FNV-1a hash of the frame data of every sheet, the animation of every state and the move list,
+/// replays of other data may play out differently.
+pub fn game_data_hash(sprite_sheets: &EditorSpriteSheets, character_animations: &CharacterAnimations, move_list: &MoveList) -> u64 {
+    let mut hasher = FnvHasher::default();
+    for id in &sprite_sheets.order {
+        let Some(sheet_info) = sprite_sheets.sheet_info(id) else { continue; };
+        hasher.write(id.as_bytes());
+        hasher.write(&serde_json::to_vec(sheet_info).unwrap_or_default());
+    }
+
+    for (index, state) in CharacterState::ALL.into_iter().enumerate() {
+        let Some(animation) = character_animations.states.get(&state) else { continue; };
+        hasher.write_usize(index);
+        hasher.write(animation.sheet_id.as_bytes());
+        hasher.write(animation.clip.as_deref().unwrap_or_default().as_bytes());
+        hasher.write(&serde_json::to_vec(&animation.loop_mode).unwrap_or_default());
+    }
+
+    hasher.write(&serde_json::to_vec(move_list).unwrap_or_default());
+    hasher.finish()
+}
+
+fn world_game_data_hash(world: &World) -> u64 {
+    game_data_hash(world.resource::<EditorSpriteSheets>(), world.resource::<CharacterAnimations>(), world.resource::<MoveList>())
+}
+
+/// Replays saved so far, most recent first.
+pub fn list_replays() -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(REPLAYS_DIRECTORY) else {
+        return Vec::new();
+    };
+
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|extension| extension == "json"))
+        .collect();
+    paths.sort();
+    paths.reverse();
+    paths
+}
+
+/// Records the match played in game mode, saved to `REPLAYS_DIRECTORY` when game mode stops.
+#[derive(Default, Resource)]
+pub struct ReplayRecorder {
+    pub recording: Option<Replay>,
+    pub last_saved: Option<PathBuf>,
+}
+
+/// A replay being played back in replay mode, with its playback controls.
+#[derive(Resource)]
+pub struct ReplayPlayback {
+    pub path: PathBuf,
+    pub replay: Replay,
+    inputs: Vec<[InputFrame; 2]>,
+    pub paused: bool,
+    /// Multiplier of the playback speed.
+    pub speed: f32,
+    /// Set by the controls, applied by `control_replay` on the next frame.
+    pub step_requested: bool,
+    pub seek_target: Option<u64>,
+    pub game_data_matches: bool,
+    /// State every `REPLAY_CHECKPOINT_INTERVAL` ticks played so far, oldest first.
+    checkpoints: Vec<GameSnapshot>,
+    /// Versus settings in place before the replay took them over, restored when replay mode stops.
+    previous_settings: Option<VersusSettings>,
+}
+
+impl ReplayPlayback {
+    pub fn new(path: PathBuf, replay: Replay) -> Self {
+        ReplayPlayback {
+            path,
+            inputs: replay.decode(),
+            replay,
+            paused: false,
+            speed: 1.0,
+            step_requested: false,
+            seek_target: None,
+            game_data_matches: true,
+            checkpoints: Vec::new(),
+            previous_settings: None,
+        }
+    }
+
+    pub fn ticks(&self) -> u64 {
+        self.inputs.len() as u64
+    }
+}
+
+fn fresh_seed() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |duration| duration.as_nanos() as u64)
+}
+
+/// Saves the recording of the match that just stopped, if anything was played.
+fn save_recording(recorder: &mut ReplayRecorder) {
+    let Some(replay) = recorder.recording.take() else { return; };
+    if replay.ticks() == 0 {
+        return;
+    }
+
+    // Several matches can stop within the same second, the counter keeps them from overwriting each other
+    let seconds = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |duration| duration.as_secs());
+    let Some(path) = (0..u32::MAX)
+        .map(|counter| Path::new(REPLAYS_DIRECTORY).join(format!("replay-{}-{}.json", seconds, counter)))
+        .find(|path| !path.exists()) else { return; };
+    match replay.save(&path) {
+        Ok(()) => {
+            info!("Saved replay to {}", path.display());
+            recorder.last_saved = Some(path);
+        }
+        Err(error) => error!("Could not save replay to {}: {}", path.display(), error),
+    }
+}
+
+/// Saves the match being recorded when the app closes, game mode never stops then.
+fn save_recording_on_exit(mut exit_events: EventReader<AppExit>, mut recorder: ResMut<ReplayRecorder>) {
+    if exit_events.read().count() > 0 {
+        save_recording(&mut recorder);
+    }
+}
+
+/// Seeds the simulation and starts recording or playing back whenever the mode changes.
+pub(crate) fn begin_match(world: &mut World, mut previous_mode: Local<Option<GameMode>>) {
+    let mode = world.resource::<GameState>().mode.clone();
+    let Some(previous) = previous_mode.replace(mode.clone()) else { return; };
+    if previous == mode {
+        return;
+    }
+
+    match previous {
+        GameMode::Game => save_recording(&mut world.resource_mut::<ReplayRecorder>()),
+        GameMode::Replay => {
+            let mut time = world.resource_mut::<Time<Virtual>>();
+            time.unpause();
+            time.set_relative_speed(1.0);
+
+            if let Some(mut playback) = world.remove_resource::<ReplayPlayback>() {
+                if let Some(settings) = playback.previous_settings.take() {
+                    world.insert_resource(settings);
+                }
+            }
+        }
+        GameMode::Editor | GameMode::Netplay | GameMode::Training => {}
+    }
+
+    match mode {
+        GameMode::Game => {
+            let seed = fresh_seed();
+            let header = ReplayHeader {
+                version: REPLAY_VERSION,
+                versus_settings: world.resource::<VersusSettings>().clone(),
+                game_data_hash: world_game_data_hash(world),
+                seed,
+            };
+            world.resource_mut::<ReplayRecorder>().recording = Some(Replay::new(header));
+            world.insert_resource(SimulationRng::new(seed));
+        }
+        GameMode::Replay => {
+            let hash = world_game_data_hash(world);
+            let Some(mut playback) = world.remove_resource::<ReplayPlayback>() else {
+                world.resource_mut::<GameState>().requested_mode = Some(GameMode::Editor);
+                return;
+            };
+
+            let header = playback.replay.header.clone();
+            playback.game_data_matches = header.game_data_hash == hash;
+            let settings = std::mem::replace(&mut *world.resource_mut::<VersusSettings>(), header.versus_settings);
+            playback.previous_settings = Some(settings);
+            world.insert_resource(playback);
+            world.insert_resource(SimulationRng::new(header.seed));
+        }
+        GameMode::Editor | GameMode::Netplay | GameMode::Training => {}
+    }
+
+    // Characters are only spawned on leaving the editor, switching between simulated modes starts them over here
+    if previous.is_simulated() && mode.is_simulated() {
+        world.run_system_once(restart_match);
+    }
+}
+
+/// Applies pause, speed, frame step and seek requests of the replay controls.
+fn control_replay(world: &mut World) {
+    if world.resource::<GameState>().mode != GameMode::Replay {
+        return;
+    }
+
+    let Some(mut playback) = world.get_resource_mut::<ReplayPlayback>() else { return; };
+    let step = std::mem::take(&mut playback.step_requested);
+    let seek_target = playback.seek_target.take();
+    let (paused, speed, ticks, seed) = (playback.paused, playback.speed, playback.ticks(), playback.replay.header.seed);
+
+    let mut time = world.resource_mut::<Time<Virtual>>();
+    if paused != time.is_paused() {
+        if paused { time.pause(); } else { time.unpause(); }
+    }
+    if speed != time.relative_speed() {
+        time.set_relative_speed(speed);
+    }
+
+    if let Some(target) = seek_target {
+        let current_tick = world.resource::<SimulationTick>().0;
+        let checkpoint = world.resource::<ReplayPlayback>().checkpoints.iter()
+            .rev()
+            .find(|checkpoint| checkpoint.tick() <= target)
+            .cloned();
+
+        match checkpoint {
+            Some(checkpoint) if target < current_tick || checkpoint.tick() > current_tick => checkpoint.restore(world),
+            // Seeking back before the first checkpoint plays the match again from its start
+            None if target < current_tick => {
+                world.run_system_once(restart_match);
+                world.insert_resource(SimulationRng::new(seed));
+            }
+            _ => {}
+        }
+
+        while world.resource::<SimulationTick>().0 < target {
+            world.run_schedule(SimulationSchedule);
+        }
+    }
+
+    if step && world.resource::<SimulationTick>().0 < ticks {
+        world.run_schedule(SimulationSchedule);
+    }
+
+    if world.resource::<SimulationTick>().0 >= ticks {
+        world.resource_mut::<ReplayPlayback>().paused = true;
+    }
+}
+
+fn save_replay_checkpoint(world: &mut World) {
+    if world.resource::<GameState>().mode != GameMode::Replay {
+        return;
+    }
+
+    let tick = world.resource::<SimulationTick>().0;
+    let Some(playback) = world.get_resource::<ReplayPlayback>() else { return; };
+    let saved = playback.checkpoints.last().is_some_and(|checkpoint| checkpoint.tick() >= tick);
+    if !tick.is_multiple_of(REPLAY_CHECKPOINT_INTERVAL) || saved {
+        return;
+    }
+
+    let checkpoint = GameSnapshot::save(world);
+    world.resource_mut::<ReplayPlayback>().checkpoints.push(checkpoint);
+}
+
+fn record_inputs(
+    game_state: Res<GameState>,
+    player_inputs: Res<PlayerInputs>,
+    simulation_tick: Res<SimulationTick>,
+    round_state: Res<RoundState>,
+    mut recorder: ResMut<ReplayRecorder>,
+) {
+    if game_state.mode != GameMode::Game {
+        return;
+    }
+
+    let Some(replay) = recorder.recording.as_mut() else { return; };
+    replay.push(player_inputs.0);
+    if round_state.rematch_requested {
+        replay.rematch_ticks.push(simulation_tick.0);
+    }
+}
+
+fn feed_replay_inputs(
+    game_state: Res<GameState>,
+    playback: Option<Res<ReplayPlayback>>,
+    simulation_tick: Res<SimulationTick>,
+    mut player_inputs: ResMut<PlayerInputs>,
+    mut round_state: ResMut<RoundState>,
+) {
+    if game_state.mode != GameMode::Replay {
+        return;
+    }
+
+    let Some(playback) = playback else { return; };
+    let index = simulation_tick.0.saturating_sub(1) as usize;
+    player_inputs.0 = playback.inputs.get(index).copied().unwrap_or_default();
+    if playback.replay.rematch_ticks.contains(&simulation_tick.0) {
+        round_state.rematch_requested = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::editor::editor_core::{EditorClipLoopMode, EditorFrameData, EditorSpriteSheetInfo};
+
+    #[test]
+    fn game_data_hash_covers_sheets_animations_and_moves() {
+        let sprite_sheets = EditorSpriteSheets::from_sheet_infos(vec![
+            EditorSpriteSheetInfo::from_frames("test", vec![EditorFrameData::empty()]),
+        ]);
+        let mut character_animations = CharacterAnimations::default();
+        let mut move_list: MoveList = serde_json::from_str(
+            r#"{ "moves": [{ "name": "Rising Jab", "motion": "623", "button": "LightPunch", "sheet_id": "test" }] }"#,
+        ).unwrap();
+        let hash = game_data_hash(&sprite_sheets, &character_animations, &move_list);
+        assert_eq!(game_data_hash(&sprite_sheets, &character_animations, &move_list), hash);
+
+        let other_sheets = EditorSpriteSheets::from_sheet_infos(vec![
+            EditorSpriteSheetInfo::from_frames("test", vec![EditorFrameData::empty(), EditorFrameData::empty()]),
+        ]);
+        assert_ne!(game_data_hash(&other_sheets, &character_animations, &move_list), hash, "sheets");
+
+        move_list.moves[0].leniency.charge_ticks += 1;
+        let moves_hash = game_data_hash(&sprite_sheets, &character_animations, &move_list);
+        assert_ne!(moves_hash, hash, "moves");
+
+        character_animations.states.get_mut(&CharacterState::Idle).unwrap().loop_mode = EditorClipLoopMode::Once;
+        assert_ne!(game_data_hash(&sprite_sheets, &character_animations, &move_list), moves_hash, "animations");
+    }
+}
@@ -0,0 +1,119 @@
+use bevy_egui::{egui, EguiContexts};
+
+use crate::core::core_core::{GameMode, GameState, ModeRequestSet};
+use crate::game::replay::*;
+use crate::game::replay::replay_core::{list_replays, Replay, ReplayPlayback, ReplayRecorder};
+use crate::game::simulation::simulation_core::SimulationTick;
+
+/// Playback speeds offered by the replay controls.
+const REPLAY_SPEEDS: [f32; 5] = [0.25, 0.5, 1.0, 2.0, 4.0];
+
+pub(crate) struct ReplayGuiPlugin;
+
+impl Plugin for ReplayGuiPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, display_replays.in_set(ModeRequestSet))
+            .add_systems(Update, display_replay_controls.in_set(ModeRequestSet));
+    }
+}
+
+/// Saved replays, shown in the editor.
+fn display_replays(
+    mut commands: Commands,
+    mut egui_contexts: EguiContexts,
+    mut game_state: ResMut<GameState>,
+    recorder: Res<ReplayRecorder>,
+    mut load_error: Local<Option<String>>,
+) {
+    if game_state.mode != GameMode::Editor {
+        return;
+    }
+
+    egui::Window::new("Replays").default_open(false).show(egui_contexts.ctx_mut(), |ui| {
+        if let Some(path) = &recorder.last_saved {
+            ui.label(format!("Last match saved to {}", path.display()));
+        }
+
+        if let Some(error) = load_error.as_ref() {
+            ui.colored_label(egui::Color32::LIGHT_RED, error);
+        }
+
+        let replays = list_replays();
+        if replays.is_empty() {
+            ui.label("Matches played in game mode are recorded here.");
+        }
+
+        egui::ScrollArea::vertical().max_height(240.0).show(ui, |ui| {
+            for path in replays {
+                ui.horizontal(|ui| {
+                    if ui.button("Play").clicked() {
+                        match Replay::load(&path) {
+                            Ok(replay) => {
+                                commands.insert_resource(ReplayPlayback::new(path.clone(), replay));
+                                game_state.requested_mode = Some(GameMode::Replay);
+                                *load_error = None;
+                            }
+                            Err(error) => *load_error = Some(format!("Could not load {}: {}", path.display(), error)),
+                        }
+                    }
+
+                    ui.label(path.file_name().map(|name| name.to_string_lossy()).unwrap_or_default());
+                });
+            }
+        });
+    });
+}
+
+fn display_replay_controls(
+    mut egui_contexts: EguiContexts,
+    mut game_state: ResMut<GameState>,
+    playback: Option<ResMut<ReplayPlayback>>,
+    simulation_tick: Res<SimulationTick>,
+    mut seek_position: Local<Option<u64>>,
+) {
+    if game_state.mode != GameMode::Replay {
+        return;
+    }
+
+    let Some(mut playback) = playback else { return; };
+
+    egui::Window::new("Replay").show(egui_contexts.ctx_mut(), |ui| {
+        ui.label(playback.path.display().to_string());
+        if !playback.game_data_matches {
+            ui.colored_label(egui::Color32::YELLOW, "Sheet data, animations or moves changed since recording, playback may differ.");
+        }
+
+        ui.horizontal(|ui| {
+            if ui.button(if playback.paused { "Play" } else { "Pause" }).clicked() {
+                playback.paused = !playback.paused;
+            }
+
+            if ui.add_enabled(playback.paused, egui::Button::new("Step")).clicked() {
+                playback.step_requested = true;
+            }
+
+            egui::ComboBox::from_id_source("replay_speed")
+                .selected_text(format!("{}x", playback.speed))
+                .show_ui(ui, |ui| {
+                    for speed in REPLAY_SPEEDS {
+                        ui.selectable_value(&mut playback.speed, speed, format!("{}x", speed));
+                    }
+                });
+
+            if ui.button("Stop").clicked() {
+                game_state.requested_mode = Some(GameMode::Editor);
+            }
+        });
+
+        // The slider follows playback until dragged, the seek happens once it is let go
+        let ticks = playback.ticks();
+        let mut position = seek_position.unwrap_or(simulation_tick.0.min(ticks));
+        let response = ui.add(egui::Slider::new(&mut position, 0..=ticks).text("Tick"));
+        if response.dragged() {
+            *seek_position = Some(position);
+        } else if response.drag_stopped() || response.changed() {
+            playback.seek_target = Some(position);
+            *seek_position = None;
+        }
+    });
+}
@@ -0,0 +1,4 @@
+pub mod round_core;
+pub mod round_gui;
+
+use bevy::prelude::*;
@@ -0,0 +1,198 @@
+use crate::core::core_core::{GameMode, GameState};
+use crate::game::character::character_core::{apply_hit_reactions, CharacterState, CharacterStateMachine, DamageEvent};
+use crate::game::collision::collision_core::ThrowEvent;
+use crate::game::game_core::Player;
+use crate::game::round::*;
+use crate::game::round::round_gui::RoundGuiPlugin;
+use crate::game::simulation::simulation_core::{SimulationSchedule, SimulationSet, TICKS_PER_SECOND};
+
+pub const MAX_HEALTH: u32 = 1000;
+
+const ROUND_SECONDS: u32 = 99;
+const ROUND_INTRO_TICKS: u32 = 90;
+const ROUND_OVER_TICKS: u32 = 150;
+
+pub(crate) struct RoundPlugin;
+
+impl Plugin for RoundPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(RoundGuiPlugin)
+            .insert_resource(RoundState::new(2))
+            .add_event::<RoundStartEvent>()
+            .add_systems(SimulationSchedule, (apply_damage, update_round)
+                .chain()
+                .after(apply_hit_reactions)
+                .in_set(SimulationSet::Reaction));
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Component)]
+pub struct Health {
+    pub current: u32,
+    pub max: u32,
+}
+
+impl Default for Health {
+    fn default() -> Self {
+        Health { current: MAX_HEALTH, max: MAX_HEALTH }
+    }
+}
+
+impl Health {
+    pub fn fraction(&self) -> f32 {
+        self.current as f32 / self.max.max(1) as f32
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RoundPhase {
+    /// Characters are in place but can't act yet.
+    Intro,
+    Fight,
+    /// `winner` is `None` for a draw, `time_over` when the timer ran out before a KO.
+    RoundOver { winner: Option<usize>, time_over: bool },
+    MatchOver { winner: Option<usize> },
+}
+
+/// Progress of a match, characters are respawned at their start positions on every `RoundStartEvent`.
+#[derive(Debug, Clone, Hash, Resource)]
+pub struct RoundState {
+    /// Starting at 1.
+    pub round: u32,
+    pub wins: [u32; 2],
+    pub rounds_to_win: u32,
+    pub phase: RoundPhase,
+    /// Ticks left before the intro or round over phase ends.
+    pub phase_ticks: u32,
+    pub timer_ticks: u32,
+    /// Set by the HUD, the simulation restarts the match on its next tick.
+    pub rematch_requested: bool,
+}
+
+impl RoundState {
+    pub fn new(rounds_to_win: u32) -> Self {
+        RoundState {
+            round: 1,
+            wins: [0; 2],
+            rounds_to_win: rounds_to_win.max(1),
+            phase: RoundPhase::Intro,
+            phase_ticks: ROUND_INTRO_TICKS,
+            timer_ticks: round_ticks(),
+            rematch_requested: false,
+        }
+    }
+
+    pub fn accepts_input(&self) -> bool {
+        self.phase == RoundPhase::Fight
+    }
+
+    /// Whole seconds left, rounded up so the timer shows 0 only once time is over.
+    pub fn timer_seconds(&self) -> u32 {
+        self.timer_ticks.div_ceil(TICKS_PER_SECOND as u32)
+    }
+
+    fn start_next_round(&mut self) {
+        self.round += 1;
+        self.phase = RoundPhase::Intro;
+        self.phase_ticks = ROUND_INTRO_TICKS;
+        self.timer_ticks = round_ticks();
+    }
+}
+
+fn round_ticks() -> u32 {
+    ROUND_SECONDS * TICKS_PER_SECOND as u32
+}
+
+/// Sent when a round starts over, after the previous one ended or on a rematch.
+#[derive(Debug, Clone, Event)]
+pub struct RoundStartEvent;
+
+fn apply_damage(
+    round_state: Res<RoundState>,
+    mut damage_events: EventReader<DamageEvent>,
+    mut throw_events: EventReader<ThrowEvent>,
+    mut query: Query<&mut Health>,
+) {
+    let damages: Vec<(Entity, u32)> = damage_events.read()
+        .map(|event| (event.entity, event.damage))
+        .chain(throw_events.read().map(|event| (event.defender, event.properties.damage)))
+        .collect();
+
+    if round_state.phase != RoundPhase::Fight {
+        return;
+    }
+
+    for (defender, damage) in damages {
+        if let Ok(mut health) = query.get_mut(defender) {
+            health.current = health.current.saturating_sub(damage);
+        }
+    }
+}
+
+pub(crate) fn update_round(
+    game_state: Res<GameState>,
+    mut round_state: ResMut<RoundState>,
+    mut round_start_events: EventWriter<RoundStartEvent>,
+    mut query: Query<(&Player, &Health, &mut CharacterStateMachine)>,
+) {
+    if !game_state.mode.is_simulated() {
+        return;
+    }
+
+    if round_state.rematch_requested {
+        *round_state = RoundState::new(round_state.rounds_to_win);
+        round_start_events.send(RoundStartEvent);
+        return;
+    }
+
+    round_state.phase_ticks = round_state.phase_ticks.saturating_sub(1);
+
+    match round_state.phase {
+        RoundPhase::Intro if round_state.phase_ticks == 0 => round_state.phase = RoundPhase::Fight,
+        // Training never ends the round, health refills instead
+        RoundPhase::Fight if game_state.mode == GameMode::Training => {}
+        RoundPhase::Fight => {
+            round_state.timer_ticks = round_state.timer_ticks.saturating_sub(1);
+
+            let mut healths = [0; 2];
+            for (player, health, _) in &query {
+                if let Some(slot) = healths.get_mut(player.index) {
+                    *slot = health.current;
+                }
+            }
+
+            let knocked_out = healths.contains(&0);
+            let time_over = round_state.timer_ticks == 0;
+            if !knocked_out && !time_over {
+                return;
+            }
+
+            let winner = match healths[0].cmp(&healths[1]) {
+                std::cmp::Ordering::Greater => Some(0),
+                std::cmp::Ordering::Less => Some(1),
+                std::cmp::Ordering::Equal => None,
+            };
+            if let Some(winner) = winner {
+                round_state.wins[winner] += 1;
+            }
+
+            round_state.phase = RoundPhase::RoundOver { winner, time_over: !knocked_out };
+            round_state.phase_ticks = ROUND_OVER_TICKS;
+
+            for (_, health, mut state_machine) in &mut query {
+                if health.current == 0 {
+                    state_machine.enter_stun(CharacterState::Knockdown, ROUND_OVER_TICKS, 0);
+                }
+            }
+        }
+        RoundPhase::RoundOver { winner, .. } if round_state.phase_ticks == 0 => {
+            if round_state.wins.iter().any(|wins| *wins >= round_state.rounds_to_win) {
+                round_state.phase = RoundPhase::MatchOver { winner };
+            } else {
+                round_state.start_next_round();
+                round_start_events.send(RoundStartEvent);
+            }
+        }
+        _ => {}
+    }
+}
@@ -0,0 +1,90 @@
+use bevy_egui::{egui, EguiContexts};
+
+use crate::core::core_core::{GameMode, GameState};
+use crate::game::game_core::Player;
+use crate::game::round::*;
+use crate::game::round::round_core::{Health, RoundPhase, RoundState};
+
+const HEALTH_BAR_WIDTH: f32 = 320.0;
+
+pub(crate) struct RoundGuiPlugin;
+
+impl Plugin for RoundGuiPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, display_round_hud);
+    }
+}
+
+/// Filled and empty pips for the rounds won out of the rounds needed.
+fn round_pips(wins: u32, rounds_to_win: u32) -> String {
+    (0..rounds_to_win).map(|round| if round < wins { '●' } else { '○' }).collect()
+}
+
+fn display_round_hud(
+    mut egui_contexts: EguiContexts,
+    game_state: Res<GameState>,
+    mut round_state: ResMut<RoundState>,
+    query: Query<(&Player, &Health)>,
+) {
+    if !game_state.mode.is_simulated() {
+        return;
+    }
+
+    let ctx = egui_contexts.ctx_mut();
+
+    let mut healths = [Health::default(); 2];
+    for (player, health) in &query {
+        if let Some(slot) = healths.get_mut(player.index) {
+            *slot = *health;
+        }
+    }
+
+    egui::Area::new(egui::Id::new("round_hud"))
+        .anchor(egui::Align2::CENTER_TOP, egui::vec2(0.0, 8.0))
+        .interactable(false)
+        .show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                for (index, health) in healths.iter().enumerate() {
+                    if index == 1 {
+                        ui.heading(format!("{:02}", round_state.timer_seconds()));
+                    }
+
+                    ui.vertical(|ui| {
+                        ui.horizontal(|ui| {
+                            ui.strong(format!("P{}", index + 1));
+                            ui.label(round_pips(round_state.wins[index], round_state.rounds_to_win));
+                        });
+                        ui.add(egui::ProgressBar::new(health.fraction())
+                            .desired_width(HEALTH_BAR_WIDTH)
+                            .text(format!("{} / {}", health.current, health.max)));
+                    });
+                }
+            });
+        });
+
+    let banner = match round_state.phase {
+        RoundPhase::Intro => Some(format!("Round {}", round_state.round)),
+        RoundPhase::Fight => None,
+        RoundPhase::RoundOver { time_over: true, .. } => Some("Time".to_string()),
+        RoundPhase::RoundOver { winner: None, .. } => Some("Double K.O.".to_string()),
+        RoundPhase::RoundOver { .. } => Some("K.O.".to_string()),
+        RoundPhase::MatchOver { winner: Some(winner) } => Some(format!("Player {} Wins", winner + 1)),
+        RoundPhase::MatchOver { winner: None } => Some("Draw".to_string()),
+    };
+
+    let Some(banner) = banner else { return; };
+
+    egui::Area::new(egui::Id::new("round_banner"))
+        .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+        .show(ctx, |ui| {
+            ui.vertical_centered(|ui| {
+                ui.label(egui::RichText::new(banner).size(48.0).strong());
+
+                // Replays request their recorded rematches themselves
+                let can_rematch = game_state.mode == GameMode::Game && matches!(round_state.phase, RoundPhase::MatchOver { .. });
+                if can_rematch && ui.button("Rematch").clicked() {
+                    round_state.rematch_requested = true;
+                }
+            });
+        });
+}
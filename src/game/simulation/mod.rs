@@ -0,0 +1,3 @@
+pub mod simulation_core;
+
+use bevy::prelude::*;
@@ -0,0 +1,151 @@
+use bevy::ecs::schedule::ScheduleLabel;
+
+use crate::core::core_core::{GameMode, GameState};
+use crate::game::replay::replay_core::ReplayPlayback;
+use crate::game::simulation::*;
+
+/// Simulation ticks per second, every frame count in frame data is in ticks.
+pub const TICKS_PER_SECOND: f64 = 60.0;
+
+/// Positions and velocities are integers in 1/`SUBPIXELS_PER_UNIT` world units, so they add up the same everywhere.
+pub const SUBPIXELS_PER_UNIT: i32 = 100;
+
+/// Advances gameplay by exactly one tick. `FixedUpdate` runs it at `TICKS_PER_SECOND` in game, replay and training mode,
+/// rollback and replay seeking run it several times in a row.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, ScheduleLabel)]
+pub struct SimulationSchedule;
+
+/// Steps of a simulation tick, one set after the other.
+/// Rendering reads the result in `Update` and never feeds back into the simulation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, SystemSet)]
+pub enum SimulationSet {
+    /// Samples the controllers into the inputs of the tick.
+    Input,
+    /// Advances state machines, physics and animations.
+    Step,
+    /// Tests boxes against each other and sends contact events.
+    Collision,
+    /// Reacts to the contact events of the tick.
+    Reaction,
+}
+
+pub(crate) struct SimulationPlugin;
+
+impl Plugin for SimulationPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Time::<Fixed>::from_hz(TICKS_PER_SECOND))
+            .insert_resource(SimulationTick::default())
+            .insert_resource(SimulationRng::new(0))
+            .init_schedule(SimulationSchedule)
+            .configure_sets(SimulationSchedule, (
+                SimulationSet::Input,
+                SimulationSet::Step,
+                SimulationSet::Collision,
+                SimulationSet::Reaction,
+            ).chain())
+            .add_systems(SimulationSchedule, advance_simulation_tick.before(SimulationSet::Input))
+            .add_systems(FixedUpdate, run_simulation)
+            .add_systems(Update, sync_transforms);
+    }
+}
+
+/// Number of the current simulation tick, counted from the start of the match.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Resource)]
+pub struct SimulationTick(pub u64);
+
+/// Random numbers drawn by the simulation, reseeded when a match starts so replays draw the same ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Resource)]
+pub struct SimulationRng {
+    state: u64,
+}
+
+impl SimulationRng {
+    pub fn new(seed: u64) -> Self {
+        SimulationRng { state: seed }
+    }
+
+    /// Next number of the splitmix64 sequence, truncated to 32 bits.
+    pub fn next_u32(&mut self) -> u32 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        (z ^ (z >> 31)) as u32
+    }
+
+    /// Uniform number in `0..bound`, `bound` has to be positive.
+    pub fn below(&mut self, bound: u32) -> u32 {
+        self.next_u32() % bound
+    }
+}
+
+/// Simulated position in subpixels, `Transform` follows it for rendering.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Component)]
+pub struct Position(pub IVec2);
+
+impl Position {
+    pub fn from_world(world: Vec2) -> Self {
+        Position((world * SUBPIXELS_PER_UNIT as f32).round().as_ivec2())
+    }
+
+    pub fn to_world(self) -> Vec2 {
+        self.0.as_vec2() / SUBPIXELS_PER_UNIT as f32
+    }
+}
+
+/// Converts a length in world units to subpixels.
+pub fn to_subpixels(world: f32) -> i32 {
+    (world * SUBPIXELS_PER_UNIT as f32).round() as i32
+}
+
+/// Netplay steps the simulation from its rollback session instead.
+/// Replays stop on their last recorded tick, even when a frame runs several ticks to catch up or play faster.
+fn run_simulation(world: &mut World) {
+    let replay_ended = world.get_resource::<ReplayPlayback>()
+        .is_some_and(|playback| world.resource::<SimulationTick>().0 >= playback.ticks());
+    match world.resource::<GameState>().mode {
+        GameMode::Game | GameMode::Training => world.run_schedule(SimulationSchedule),
+        GameMode::Replay if !replay_ended => world.run_schedule(SimulationSchedule),
+        GameMode::Replay | GameMode::Editor | GameMode::Netplay => {}
+    }
+}
+
+fn advance_simulation_tick(
+    game_state: Res<GameState>,
+    mut tick: ResMut<SimulationTick>,
+) {
+    if !game_state.mode.is_simulated() {
+        return;
+    }
+
+    tick.0 += 1;
+}
+
+fn sync_transforms(mut query: Query<(&Position, &mut Transform), Changed<Position>>) {
+    for (position, mut transform) in &mut query {
+        transform.translation = position.to_world().extend(transform.translation.z);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::game_core::{test_match_world, Controller, VersusSettings};
+    use crate::game::input::input_core::InputFrame;
+    use crate::game::replay::replay_core::{Replay, ReplayHeader};
+
+    #[test]
+    fn replays_stop_on_their_last_recorded_tick() {
+        let mut world = test_match_world(GameMode::Replay, Controller::Human);
+        let mut replay = Replay::new(ReplayHeader { version: 0, versus_settings: VersusSettings::default(), game_data_hash: 0, seed: 7 });
+        for _ in 0..30 {
+            replay.push([InputFrame::default(); 2]);
+        }
+        world.insert_resource(ReplayPlayback::new("test.json".into(), replay));
+
+        for _ in 0..40 {
+            run_simulation(&mut world);
+        }
+        assert_eq!(world.resource::<SimulationTick>().0, 30);
+    }
+}
@@ -0,0 +1,3 @@
+pub mod snapshot_core;
+
+use bevy::prelude::*;
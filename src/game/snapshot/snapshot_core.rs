@@ -0,0 +1,194 @@
+use std::hash::{Hash, Hasher};
+
+use crate::game::character::character_core::{CharacterInput, CharacterStateMachine, FrameAdvantage};
+use crate::game::collision::collision_core::{CollisionBody, Facing, HitTracker};
+use crate::game::game_core::{Player, SpriteAnimation};
+use crate::game::input::input_core::InputHistory;
+use crate::game::round::round_core::{Health, RoundState};
+use crate::game::simulation::simulation_core::{Position, SimulationRng, SimulationTick};
+use crate::game::snapshot::*;
+
+/// 64-bit FNV-1a, unlike the std hashers its output is the same on every build and platform.
+pub struct FnvHasher(u64);
+
+impl Default for FnvHasher {
+    fn default() -> Self {
+        FnvHasher(0xCBF2_9CE4_8422_2325)
+    }
+}
+
+impl Hasher for FnvHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(0x0000_0100_0000_01B3);
+        }
+    }
+}
+
+/// Simulated components of one character.
+#[derive(Debug, Clone)]
+struct CharacterSnapshot {
+    entity: Entity,
+    index: usize,
+    position: Position,
+    facing: Facing,
+    state_machine: CharacterStateMachine,
+    input: CharacterInput,
+    history: InputHistory,
+    health: Health,
+    hit_tracker: HitTracker,
+    animation: SpriteAnimation,
+    body: CollisionBody,
+}
+
+/// Everything a simulation tick reads, restoring it puts the game back to the tick it was saved on.
+/// Characters are restored into the entities they were saved from, which round restarts keep alive.
+#[derive(Debug, Clone)]
+pub struct GameSnapshot {
+    tick: SimulationTick,
+    rng: SimulationRng,
+    round_state: RoundState,
+    frame_advantage: FrameAdvantage,
+    characters: Vec<CharacterSnapshot>,
+}
+
+impl GameSnapshot {
+    pub fn save(world: &mut World) -> Self {
+        let mut query = world.query::<(
+            Entity,
+            &Player,
+            &Position,
+            &Facing,
+            &CharacterStateMachine,
+            &CharacterInput,
+            &InputHistory,
+            &Health,
+            &HitTracker,
+            &SpriteAnimation,
+            &CollisionBody,
+        )>();
+
+        let mut characters: Vec<CharacterSnapshot> = query.iter(world)
+            .map(|(entity, player, position, facing, state_machine, input, history, health, hit_tracker, animation, body)| CharacterSnapshot {
+                entity,
+                index: player.index,
+                position: *position,
+                facing: *facing,
+                state_machine: state_machine.clone(),
+                input: *input,
+                history: history.clone(),
+                health: *health,
+                hit_tracker: hit_tracker.clone(),
+                animation: animation.clone(),
+                body: body.clone(),
+            })
+            .collect();
+        characters.sort_by_key(|character| character.index);
+
+        GameSnapshot {
+            tick: *world.resource::<SimulationTick>(),
+            rng: *world.resource::<SimulationRng>(),
+            round_state: world.resource::<RoundState>().clone(),
+            frame_advantage: world.resource::<FrameAdvantage>().clone(),
+            characters,
+        }
+    }
+
+    /// Tick the snapshot was saved after.
+    pub fn tick(&self) -> u64 {
+        self.tick.0
+    }
+
+    pub fn restore(&self, world: &mut World) {
+        world.insert_resource(self.tick);
+        world.insert_resource(self.rng);
+        world.insert_resource(self.round_state.clone());
+        world.insert_resource(self.frame_advantage.clone());
+
+        for character in &self.characters {
+            let Some(mut entity) = world.get_entity_mut(character.entity) else { continue; };
+            entity.insert((
+                character.position,
+                character.facing,
+                character.state_machine.clone(),
+                character.input,
+                character.history.clone(),
+                character.health,
+                character.hit_tracker.clone(),
+                character.animation.clone(),
+                character.body.clone(),
+            ));
+        }
+    }
+
+    /// Hash of the simulated state, equal on every peer that simulated the same inputs.
+    /// Entities differ between peers, so characters are told apart by player index instead.
+    pub fn checksum(&self) -> u64 {
+        let mut hasher = FnvHasher::default();
+        self.tick.0.hash(&mut hasher);
+        self.rng.hash(&mut hasher);
+        self.round_state.hash(&mut hasher);
+        self.frame_advantage.last.map(|result| (result.ticks, result.blocked)).hash(&mut hasher);
+
+        for character in &self.characters {
+            character.index.hash(&mut hasher);
+            character.position.hash(&mut hasher);
+            character.facing.hash(&mut hasher);
+            character.state_machine.hash(&mut hasher);
+            character.input.hash(&mut hasher);
+            character.history.hash(&mut hasher);
+            character.health.hash(&mut hasher);
+
+            let playback = &character.animation.playback;
+            character.animation.sheet_id.hash(&mut hasher);
+            (playback.frame, playback.ticks, playback.reverse, playback.finished).hash(&mut hasher);
+
+            for (defender, hit_group) in character.hit_tracker.connected() {
+                let defender_index = self.characters.iter().find(|other| other.entity == *defender).map(|other| other.index);
+                (defender_index, hit_group).hash(&mut hasher);
+            }
+        }
+
+        hasher.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::core_core::GameMode;
+    use crate::game::game_core::{test_match_world, Controller};
+    use crate::game::simulation::simulation_core::SimulationSchedule;
+
+    fn run_ticks(world: &mut World, ticks: u64) {
+        for _ in 0..ticks {
+            world.run_schedule(SimulationSchedule);
+        }
+    }
+
+    #[test]
+    fn restored_snapshots_simulate_the_same_ticks() {
+        let mut world = test_match_world(GameMode::Game, Controller::Random);
+        run_ticks(&mut world, 120);
+        let saved = GameSnapshot::save(&mut world);
+        assert_eq!(saved.characters.len(), 2);
+
+        run_ticks(&mut world, 60);
+        let expected = GameSnapshot::save(&mut world);
+        assert_ne!(expected.checksum(), saved.checksum(), "the simulation advanced");
+
+        saved.restore(&mut world);
+        assert_eq!(GameSnapshot::save(&mut world).checksum(), saved.checksum());
+        run_ticks(&mut world, 60);
+        let actual = GameSnapshot::save(&mut world);
+
+        assert_eq!(actual.tick(), expected.tick());
+        assert_eq!(actual.checksum(), expected.checksum());
+        assert_eq!(format!("{:?}", actual), format!("{:?}", expected));
+    }
+}
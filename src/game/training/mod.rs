@@ -0,0 +1,4 @@
+pub mod training_core;
+pub mod training_gui;
+
+use bevy::prelude::*;
@@ -0,0 +1,479 @@
+use bevy::utils::HashMap;
+
+use crate::core::core_core::{GameMode, GameState};
+use crate::editor::editor_core::{EditorBoxKind, EditorSpriteSheets};
+use crate::game::character::character_core::{
+    read_character_input, CharacterAnimations, CharacterState, CharacterStateMachine, DamageEvent, FrameAdvantage,
+    GROUND_Y, STAGE_HALF_WIDTH,
+};
+use crate::game::collision::collision_core::{CollisionBody, Facing, ThrowEvent};
+use crate::game::game_core::{spawn_characters, Player, VersusSettings};
+use crate::game::input::input_core::{sample_player_inputs, InputButton, InputFrame, PlayerInputs};
+use crate::game::moves::moves_core::MoveList;
+use crate::game::round::round_core::{update_round, Health};
+use crate::game::simulation::simulation_core::{SimulationRng, SimulationSchedule, SimulationSet, SUBPIXELS_PER_UNIT};
+use crate::game::training::*;
+use crate::game::training::training_gui::TrainingGuiPlugin;
+
+/// Player index of the dummy.
+const DUMMY_PLAYER: usize = 1;
+
+/// Ticks both characters have to stay neutral before a dummy blocking after the first hit lowers its guard again.
+const GUARD_RESET_TICKS: u32 = 30;
+
+/// Distance between the characters after a position reset, in world units.
+const RESET_SPACING: f32 = 200.0;
+
+/// Distance between the wall and the cornered character after a corner reset, in world units.
+const CORNER_MARGIN: f32 = 40.0;
+
+pub const RESET_POSITIONS_KEY: KeyCode = KeyCode::KeyR;
+pub const RECORD_KEY: KeyCode = KeyCode::F5;
+pub const PLAYBACK_KEY: KeyCode = KeyCode::F6;
+
+pub(crate) struct TrainingPlugin;
+
+impl Plugin for TrainingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(TrainingGuiPlugin)
+            .insert_resource(TrainingSettings::default())
+            .insert_resource(TrainingDummy::default())
+            .insert_resource(LiveFrameData::default())
+            .add_systems(Update, read_training_hotkeys)
+            .add_systems(SimulationSchedule, drive_dummy
+                .after(sample_player_inputs)
+                .before(read_character_input)
+                .in_set(SimulationSet::Input))
+            .add_systems(SimulationSchedule, (measure_frame_data, refill_health, reset_positions)
+                .chain()
+                .after(update_round)
+                .in_set(SimulationSet::Reaction));
+    }
+}
+
+/// What the dummy does while it isn't playing back a recording.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum DummyStance {
+    #[default]
+    Stand,
+    Crouch,
+    Jump,
+}
+
+impl DummyStance {
+    pub const ALL: [DummyStance; 3] = [DummyStance::Stand, DummyStance::Crouch, DummyStance::Jump];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            DummyStance::Stand => "Stand",
+            DummyStance::Crouch => "Crouch",
+            DummyStance::Jump => "Jump",
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum DummyBlock {
+    #[default]
+    None,
+    All,
+    /// Lets the first hit of a string through and blocks the rest.
+    AfterFirstHit,
+    /// Decides on every attack of the player whether to block it.
+    Random,
+}
+
+impl DummyBlock {
+    pub const ALL: [DummyBlock; 4] = [DummyBlock::None, DummyBlock::All, DummyBlock::AfterFirstHit, DummyBlock::Random];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            DummyBlock::None => "None",
+            DummyBlock::All => "All",
+            DummyBlock::AfterFirstHit => "After First Hit",
+            DummyBlock::Random => "Random",
+        }
+    }
+}
+
+/// Where the position reset puts the characters.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ResetPosition {
+    #[default]
+    Midscreen,
+    LeftCorner,
+    RightCorner,
+}
+
+impl ResetPosition {
+    pub const ALL: [ResetPosition; 3] = [ResetPosition::Midscreen, ResetPosition::LeftCorner, ResetPosition::RightCorner];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ResetPosition::Midscreen => "Midscreen",
+            ResetPosition::LeftCorner => "Left Corner",
+            ResetPosition::RightCorner => "Right Corner",
+        }
+    }
+
+    /// World positions of player one and two, the dummy is the cornered one unless `player_cornered`.
+    pub fn positions(&self, player_cornered: bool) -> [Vec2; 2] {
+        let wall = STAGE_HALF_WIDTH as f32 / SUBPIXELS_PER_UNIT as f32 - CORNER_MARGIN;
+        let ground = GROUND_Y as f32 / SUBPIXELS_PER_UNIT as f32;
+        let [player, dummy] = match self {
+            ResetPosition::Midscreen => return [Vec2::new(-RESET_SPACING / 2.0, ground), Vec2::new(RESET_SPACING / 2.0, ground)],
+            ResetPosition::LeftCorner => [-wall + RESET_SPACING, -wall],
+            ResetPosition::RightCorner => [wall - RESET_SPACING, wall],
+        };
+
+        let [player, dummy] = if player_cornered { [dummy, player] } else { [player, dummy] };
+        [Vec2::new(player, ground), Vec2::new(dummy, ground)]
+    }
+}
+
+/// Dummy options, edited from the training window.
+#[derive(Debug, Default, Clone, PartialEq, Resource)]
+pub struct TrainingSettings {
+    pub stance: DummyStance,
+    pub block: DummyBlock,
+    pub reset_position: ResetPosition,
+    /// Puts player one in the corner on corner resets instead of the dummy.
+    pub player_cornered: bool,
+    /// Starts the recording over when its playback ends.
+    pub loop_playback: bool,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum RecordingState {
+    #[default]
+    Idle,
+    /// Player one's inputs drive the dummy and are recorded.
+    Recording,
+    /// The dummy plays back the recording, `tick` is the next recorded frame.
+    Playing { tick: usize },
+}
+
+/// Runtime state of the dummy.
+#[derive(Debug, Default, Resource)]
+pub struct TrainingDummy {
+    pub recording_state: RecordingState,
+    /// Recorded inputs relative to the dummy's facing, as if it faced right.
+    pub recording: Vec<InputFrame>,
+    /// Set by the hotkey and the training window, applied on the next tick.
+    pub reset_requested: bool,
+    /// Whether the dummy blocks the current string, for the blocking options that don't always block.
+    guard_up: bool,
+    /// Ticks both characters spent neutral since the last attack.
+    neutral_ticks: u32,
+}
+
+impl TrainingDummy {
+    pub fn toggle_recording(&mut self) {
+        self.recording_state = match self.recording_state {
+            RecordingState::Recording => RecordingState::Idle,
+            _ => {
+                self.recording.clear();
+                RecordingState::Recording
+            }
+        };
+    }
+
+    pub fn toggle_playback(&mut self) {
+        self.recording_state = match self.recording_state {
+            RecordingState::Idle if !self.recording.is_empty() => RecordingState::Playing { tick: 0 },
+            _ => RecordingState::Idle,
+        };
+    }
+}
+
+fn read_training_hotkeys(
+    game_state: Res<GameState>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut dummy: ResMut<TrainingDummy>,
+) {
+    if game_state.mode != GameMode::Training {
+        return;
+    }
+
+    if keyboard.just_pressed(RESET_POSITIONS_KEY) {
+        dummy.reset_requested = true;
+    }
+    if keyboard.just_pressed(RECORD_KEY) {
+        dummy.toggle_recording();
+    }
+    if keyboard.just_pressed(PLAYBACK_KEY) {
+        dummy.toggle_playback();
+    }
+}
+
+fn is_attacking(state: CharacterState) -> bool {
+    matches!(state, CharacterState::Attack(_) | CharacterState::Special(_))
+}
+
+/// Replaces player two's inputs with the dummy's, or with player one's while recording.
+fn drive_dummy(
+    game_state: Res<GameState>,
+    settings: Res<TrainingSettings>,
+    mut dummy: ResMut<TrainingDummy>,
+    mut player_inputs: ResMut<PlayerInputs>,
+    mut rng: ResMut<SimulationRng>,
+    query: Query<(&Player, &CharacterStateMachine, &Facing)>,
+) {
+    if game_state.mode != GameMode::Training {
+        return;
+    }
+
+    let mut dummy_state = None;
+    let mut opponent_state = None;
+    for (player, state_machine, facing) in &query {
+        if player.index == DUMMY_PLAYER {
+            dummy_state = Some((state_machine, *facing));
+        } else {
+            opponent_state = Some(state_machine);
+        }
+    }
+    let (Some((dummy_state, facing)), Some(opponent_state)) = (dummy_state, opponent_state) else { return; };
+
+    // Recordings are relative to the facing so they play back the same on either side, mirroring converts both ways
+    let flip = |frame: InputFrame| if facing == Facing::Left { frame.mirrored() } else { frame };
+
+    match dummy.recording_state {
+        RecordingState::Recording => {
+            let frame = player_inputs.0[0];
+            player_inputs.0 = [InputFrame::default(), frame];
+            dummy.recording.push(flip(frame));
+            return;
+        }
+        RecordingState::Playing { tick } => {
+            let tick = if tick >= dummy.recording.len() && settings.loop_playback { 0 } else { tick };
+            if let Some(frame) = dummy.recording.get(tick).copied() {
+                player_inputs.0[DUMMY_PLAYER] = flip(frame);
+                dummy.recording_state = RecordingState::Playing { tick: tick + 1 };
+                return;
+            }
+            dummy.recording_state = RecordingState::Idle;
+        }
+        RecordingState::Idle => {}
+    }
+
+    let opponent_attacking = is_attacking(opponent_state.state);
+    if opponent_attacking || !dummy_state.state.is_actionable() {
+        dummy.neutral_ticks = 0;
+    } else {
+        dummy.neutral_ticks += 1;
+    }
+
+    match settings.block {
+        DummyBlock::None => dummy.guard_up = false,
+        DummyBlock::All => dummy.guard_up = true,
+        DummyBlock::AfterFirstHit => {
+            if dummy_state.state == CharacterState::Hitstun || dummy_state.state.is_juggled() {
+                dummy.guard_up = true;
+            } else if dummy.neutral_ticks >= GUARD_RESET_TICKS {
+                dummy.guard_up = false;
+            }
+        }
+        DummyBlock::Random => {
+            if opponent_attacking && opponent_state.ticks == 0 {
+                dummy.guard_up = rng.below(2) == 0;
+            }
+        }
+    }
+
+    let mut frame = InputFrame::default();
+    let blocking = dummy.guard_up && (opponent_attacking || dummy_state.state == CharacterState::Blockstun);
+    match settings.stance {
+        DummyStance::Crouch => frame.set(InputButton::Down, true),
+        // Jumping back would get away instead of blocking
+        DummyStance::Jump => frame.set(InputButton::Up, !blocking),
+        DummyStance::Stand => {}
+    }
+    frame.set(InputButton::Left, blocking);
+
+    player_inputs.0[DUMMY_PLAYER] = flip(frame);
+}
+
+/// Health refills as soon as a character can act again, so training never runs out of it.
+fn refill_health(
+    game_state: Res<GameState>,
+    mut query: Query<(&CharacterStateMachine, &mut Health)>,
+) {
+    if game_state.mode != GameMode::Training {
+        return;
+    }
+
+    for (state_machine, mut health) in &mut query {
+        if state_machine.state.is_actionable() && health.current < health.max {
+            health.current = health.max;
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn reset_positions(
+    mut commands: Commands,
+    game_state: Res<GameState>,
+    settings: Res<TrainingSettings>,
+    mut dummy: ResMut<TrainingDummy>,
+    sprite_sheets: Res<EditorSpriteSheets>,
+    character_animations: Res<CharacterAnimations>,
+    versus_settings: Res<VersusSettings>,
+    query: Query<(Entity, &Player)>,
+) {
+    if game_state.mode != GameMode::Training || !std::mem::take(&mut dummy.reset_requested) {
+        return;
+    }
+
+    let mut versus_settings = versus_settings.clone();
+    let positions = settings.reset_position.positions(settings.player_cornered);
+    for (player_settings, position) in versus_settings.players.iter_mut().zip(positions) {
+        player_settings.start_position = position;
+    }
+
+    let entities: HashMap<usize, Entity> = query.iter().map(|(entity, player)| (player.index, entity)).collect();
+    spawn_characters(&mut commands, &sprite_sheets, &character_animations, &versus_settings, &entities);
+}
+
+/// Frame data of a move of player one, counted in ticks of its state so hitstop doesn't count.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MeasuredMove {
+    pub name: String,
+    /// Tick of the move its first hit or throw box was out on, counting the tick it started on as 1.
+    pub startup: Option<u32>,
+    /// Ticks from the first to the last one with a hit or throw box out.
+    pub active: u32,
+    /// Ticks after the last active one until player one could act again, `None` until then.
+    pub recovery: Option<u32>,
+    pub on_hit: Option<i32>,
+    pub on_block: Option<i32>,
+    /// Damage dealt to the dummy while the move ran, chip damage included.
+    pub damage: u32,
+    /// Ticks the move ran so far.
+    ticks: u32,
+    last_active: Option<u32>,
+    /// Cleared once the move recovered or was interrupted.
+    running: bool,
+}
+
+impl MeasuredMove {
+    fn new(name: String) -> Self {
+        MeasuredMove {
+            name,
+            startup: None,
+            active: 0,
+            recovery: None,
+            on_hit: None,
+            on_block: None,
+            damage: 0,
+            ticks: 0,
+            last_active: None,
+            running: true,
+        }
+    }
+}
+
+/// Frame data of the last move of player one and the current combo on the dummy, shown by the training HUD.
+#[derive(Debug, Default, Resource)]
+pub struct LiveFrameData {
+    pub last_move: Option<MeasuredMove>,
+    pub combo_hits: u32,
+    pub combo_damage: u32,
+    /// Whether the dummy was still reeling from the combo on the previous tick.
+    combo_running: bool,
+    /// `FrameAdvantage::measured` on the previous tick.
+    advantages_seen: u64,
+}
+
+fn is_reeling(state: CharacterState) -> bool {
+    matches!(state, CharacterState::Hitstun | CharacterState::Knockdown) || state.is_juggled()
+}
+
+#[allow(clippy::too_many_arguments)]
+fn measure_frame_data(
+    game_state: Res<GameState>,
+    sprite_sheets: Res<EditorSpriteSheets>,
+    move_list: Res<MoveList>,
+    frame_advantage: Res<FrameAdvantage>,
+    mut live_frame_data: ResMut<LiveFrameData>,
+    mut damage_events: EventReader<DamageEvent>,
+    mut throw_events: EventReader<ThrowEvent>,
+    query: Query<(Entity, &Player, &CharacterStateMachine, &CollisionBody)>,
+) {
+    let damages: Vec<(Entity, u32)> = damage_events.read()
+        .map(|event| (event.entity, event.damage))
+        .chain(throw_events.read().map(|event| (event.defender, event.properties.damage)))
+        .collect();
+
+    if game_state.mode != GameMode::Training {
+        return;
+    }
+
+    let mut player = None;
+    let mut dummy = None;
+    for (entity, character, state_machine, body) in &query {
+        if character.index == DUMMY_PLAYER {
+            dummy = Some((entity, state_machine));
+        } else {
+            player = Some((entity, state_machine, body));
+        }
+    }
+    let (Some((player, state_machine, body)), Some((dummy, dummy_state))) = (player, dummy) else { return; };
+
+    let live_frame_data = &mut *live_frame_data;
+    let dummy_damages: Vec<u32> = damages.iter().filter(|(entity, _)| *entity == dummy).map(|(_, damage)| *damage).collect();
+    let damage: u32 = dummy_damages.iter().sum();
+
+    // Chip damage leaves the dummy in blockstun, only damage that put it in a hit state counts toward the combo
+    if is_reeling(dummy_state.state) && !dummy_damages.is_empty() {
+        if !live_frame_data.combo_running {
+            live_frame_data.combo_hits = 0;
+            live_frame_data.combo_damage = 0;
+        }
+        live_frame_data.combo_hits += dummy_damages.len() as u32;
+        live_frame_data.combo_damage += damage;
+    }
+    live_frame_data.combo_running = is_reeling(dummy_state.state);
+
+    if frame_advantage.measured != live_frame_data.advantages_seen {
+        live_frame_data.advantages_seen = frame_advantage.measured;
+        let result = frame_advantage.last.filter(|result| result.attacker == player);
+        if let (Some(result), Some(last_move)) = (result, live_frame_data.last_move.as_mut()) {
+            if result.blocked { last_move.on_block = Some(result.ticks); } else { last_move.on_hit = Some(result.ticks); }
+        }
+    }
+
+    let name = match state_machine.state {
+        CharacterState::Special(index) => move_list.moves.get(index).map(|definition| definition.name.clone()),
+        state @ CharacterState::Attack(_) => Some(state.label().to_string()),
+        _ => None,
+    };
+    // A move stays on its first tick during hitstop when it hits right away
+    let first_tick = state_machine.ticks == 0
+        && !live_frame_data.last_move.as_ref().is_some_and(|last_move| last_move.running && last_move.ticks == 1);
+    if let Some(name) = name.filter(|_| first_tick) {
+        live_frame_data.last_move = Some(MeasuredMove::new(name));
+    }
+
+    let Some(last_move) = live_frame_data.last_move.as_mut().filter(|last_move| last_move.running) else { return; };
+    last_move.damage += damage;
+
+    if !is_attacking(state_machine.state) {
+        // Moves interrupted by a hit have no recovery
+        if state_machine.state.is_actionable() {
+            last_move.recovery = Some(last_move.ticks - last_move.last_active.unwrap_or(0));
+        }
+        last_move.running = false;
+        return;
+    }
+
+    last_move.ticks = state_machine.ticks + 1;
+    let frame_data = sprite_sheets.sheet_info(&body.sheet_id).and_then(|sheet_info| sheet_info.frames.get(body.frame));
+    let active = frame_data.is_some_and(|frame_data| {
+        [EditorBoxKind::Hit, EditorBoxKind::Throw].into_iter().any(|kind| frame_data.active_boxes(kind).next().is_some())
+    });
+    if active {
+        let startup = *last_move.startup.get_or_insert(last_move.ticks);
+        last_move.last_active = Some(last_move.ticks);
+        last_move.active = last_move.ticks - startup + 1;
+    }
+}
@@ -0,0 +1,134 @@
+use bevy_egui::{egui, EguiContexts};
+
+use crate::core::core_core::{GameMode, GameState};
+use crate::game::training::*;
+use crate::game::training::training_core::{
+    DummyBlock, DummyStance, LiveFrameData, RecordingState, ResetPosition, TrainingDummy, TrainingSettings, PLAYBACK_KEY,
+    RECORD_KEY, RESET_POSITIONS_KEY,
+};
+
+pub(crate) struct TrainingGuiPlugin;
+
+impl Plugin for TrainingGuiPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, display_training_options)
+            .add_systems(Update, display_frame_data);
+    }
+}
+
+fn display_training_options(
+    mut egui_contexts: EguiContexts,
+    game_state: Res<GameState>,
+    mut settings: ResMut<TrainingSettings>,
+    mut dummy: ResMut<TrainingDummy>,
+) {
+    if game_state.mode != GameMode::Training {
+        return;
+    }
+
+    egui::Window::new("Training").show(egui_contexts.ctx_mut(), |ui| {
+        ui.strong("Dummy");
+        ui.horizontal(|ui| {
+            ui.label("Stance");
+            for stance in DummyStance::ALL {
+                ui.radio_value(&mut settings.stance, stance, stance.label());
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label("Block");
+            for block in DummyBlock::ALL {
+                ui.radio_value(&mut settings.block, block, block.label());
+            }
+        });
+
+        ui.separator();
+        ui.strong("Recording");
+        ui.horizontal(|ui| {
+            let recording = dummy.recording_state == RecordingState::Recording;
+            let record_text = if recording { "Stop Recording" } else { "Record" };
+            if ui.button(format!("{} ({:?})", record_text, RECORD_KEY)).clicked() {
+                dummy.toggle_recording();
+            }
+
+            let playing = matches!(dummy.recording_state, RecordingState::Playing { .. });
+            let playback_text = if playing { "Stop" } else { "Play" };
+            let can_play = playing || (!recording && !dummy.recording.is_empty());
+            if ui.add_enabled(can_play, egui::Button::new(format!("{} ({:?})", playback_text, PLAYBACK_KEY))).clicked() {
+                dummy.toggle_playback();
+            }
+
+            ui.checkbox(&mut settings.loop_playback, "Loop");
+        });
+        ui.label(match dummy.recording_state {
+            RecordingState::Recording => format!("Recording the dummy, {} ticks", dummy.recording.len()),
+            RecordingState::Playing { tick } => format!("Playing tick {} of {}", tick, dummy.recording.len()),
+            RecordingState::Idle => format!("{} ticks recorded", dummy.recording.len()),
+        });
+
+        ui.separator();
+        ui.strong("Position Reset");
+        ui.horizontal(|ui| {
+            for position in ResetPosition::ALL {
+                ui.radio_value(&mut settings.reset_position, position, position.label());
+            }
+        });
+        ui.checkbox(&mut settings.player_cornered, "Player in Corner");
+        if ui.button(format!("Reset Positions ({:?})", RESET_POSITIONS_KEY)).clicked() {
+            dummy.reset_requested = true;
+        }
+    });
+}
+
+fn format_ticks(ticks: Option<u32>) -> String {
+    ticks.map_or("-".to_string(), |ticks| ticks.to_string())
+}
+
+fn format_advantage(ticks: Option<i32>) -> String {
+    ticks.map_or("-".to_string(), |ticks| format!("{:+}", ticks))
+}
+
+/// Frame data of player one's last move as measured by the simulation, next to the combo on the dummy.
+fn display_frame_data(
+    mut egui_contexts: EguiContexts,
+    game_state: Res<GameState>,
+    live_frame_data: Res<LiveFrameData>,
+) {
+    if game_state.mode != GameMode::Training {
+        return;
+    }
+
+    egui::Area::new(egui::Id::new("training_frame_data"))
+        .anchor(egui::Align2::RIGHT_TOP, egui::vec2(-8.0, 8.0))
+        .interactable(false)
+        .show(egui_contexts.ctx_mut(), |ui| {
+            egui::Frame::popup(ui.style()).show(ui, |ui| {
+                match &live_frame_data.last_move {
+                    Some(last_move) => {
+                        ui.strong(&last_move.name);
+                        egui::Grid::new("training_frame_data_grid").show(ui, |ui| {
+                            let rows = [
+                                ("Startup", format_ticks(last_move.startup)),
+                                ("Active", format_ticks((last_move.active > 0).then_some(last_move.active))),
+                                ("Recovery", format_ticks(last_move.recovery)),
+                                ("On Hit", format_advantage(last_move.on_hit)),
+                                ("On Block", format_advantage(last_move.on_block)),
+                                ("Damage", last_move.damage.to_string()),
+                            ];
+                            for (label, value) in rows {
+                                ui.label(label);
+                                ui.monospace(value);
+                                ui.end_row();
+                            }
+                        });
+                    }
+                    None => {
+                        ui.strong("Frame Data");
+                        ui.label("Attack the dummy to measure a move.");
+                    }
+                }
+
+                ui.separator();
+                ui.monospace(format!("Combo {} hits, {} damage", live_frame_data.combo_hits, live_frame_data.combo_damage));
+            });
+        });
+}
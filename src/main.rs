@@ -16,7 +16,13 @@ use crate::game::game_core::GamePlugin;
 
 fn main() {
     App::new()
-        .add_plugins(DefaultPlugins.set(ImagePlugin::default_nearest()))
+        .add_plugins(DefaultPlugins
+            .set(ImagePlugin::default_nearest())
+            // Closing is handled by the editor, which asks about unsaved changes first
+            .set(WindowPlugin {
+                close_when_requested: false,
+                ..default()
+            }))
         .add_plugins(CorePlugin)
         .add_plugins(EditorPlugin)
         .add_plugins(GamePlugin)